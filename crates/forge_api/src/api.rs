@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -77,4 +77,30 @@ impl<F: App + Infrastructure> API for ForgeAPI<F> {
     ) -> anyhow::Result<Option<Conversation>> {
         self.app.conversation_service().get(conversation_id).await
     }
+
+    async fn conversations(&self) -> anyhow::Result<Vec<Conversation>> {
+        self.app.conversation_service().list().await
+    }
+
+    async fn upsert_conversation(&self, conversation: Conversation) -> anyhow::Result<()> {
+        self.app.conversation_service().upsert(conversation).await
+    }
+
+    async fn checkpoints(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<Vec<Checkpoint>> {
+        self.app.checkpoint_service().list(conversation_id).await
+    }
+
+    async fn undo(
+        &self,
+        conversation_id: &ConversationId,
+        turn: u64,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        self.app
+            .checkpoint_service()
+            .restore(conversation_id, turn)
+            .await
+    }
 }
@@ -43,13 +43,15 @@ impl<F: Infrastructure + App> ForgeExecutorService<F> {
 
         let app = self.app.clone();
 
-        Ok(MpscStream::spawn(move |tx| async move {
-            let tx = Arc::new(tx);
-            let orch = Orchestrator::new(app, request, ctx, Some(tx.clone()));
-            match orch.execute().await {
-                Ok(_) => {}
-                Err(err) => tx.send(Err(err)).await.unwrap(),
-            }
-        }))
+        Ok(MpscStream::spawn_cancellable(
+            move |tx, cancel| async move {
+                let tx = Arc::new(tx);
+                let orch = Orchestrator::new(app, request, ctx, Some(tx.clone()), cancel);
+                match orch.execute().await {
+                    Ok(_) => {}
+                    Err(err) => tx.send(Err(err)).await.unwrap(),
+                }
+            },
+        ))
     }
 }
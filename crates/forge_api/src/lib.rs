@@ -3,11 +3,11 @@ mod executor;
 mod loader;
 mod suggestion;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub use api::*;
 pub use forge_domain::*;
-use forge_stream::MpscStream;
+pub use forge_stream::MpscStream;
 
 #[async_trait::async_trait]
 pub trait API {
@@ -44,4 +44,25 @@ pub trait API {
         &self,
         conversation_id: &ConversationId,
     ) -> anyhow::Result<Option<Conversation>>;
+
+    /// Lists all the stored conversations, most recently active first
+    async fn conversations(&self) -> anyhow::Result<Vec<Conversation>>;
+
+    /// Stores the given conversation so that it can be continued, replacing
+    /// any existing conversation with the same ID
+    async fn upsert_conversation(&self, conversation: Conversation) -> anyhow::Result<()>;
+
+    /// Lists the checkpoints of the conversation, oldest turn first
+    async fn checkpoints(
+        &self,
+        conversation_id: &ConversationId,
+    ) -> anyhow::Result<Vec<Checkpoint>>;
+
+    /// Restores the files modified by tools in or after the given turn to
+    /// their previous content. Returns the restored files.
+    async fn undo(
+        &self,
+        conversation_id: &ConversationId,
+        turn: u64,
+    ) -> anyhow::Result<Vec<PathBuf>>;
 }
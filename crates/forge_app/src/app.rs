@@ -2,12 +2,13 @@ use std::sync::Arc;
 
 use forge_domain::App;
 
+use crate::checkpoint::ForgeCheckpointService;
 use crate::conversation::ForgeConversationService;
-use crate::provider::ForgeProviderService;
+use crate::provider::{ForgeProviderRegistry, ForgeProviderService};
 use crate::suggestion::ForgeSuggestionService;
 use crate::template::ForgeTemplateService;
 use crate::tool_service::ForgeToolService;
-use crate::Infrastructure;
+use crate::{EnvironmentService, Infrastructure};
 
 /// ForgeApp is the main application container that implements the App trait.
 /// It provides access to all core services required by the application.
@@ -19,21 +20,26 @@ pub struct ForgeApp<F> {
     infra: Arc<F>,
     tool_service: ForgeToolService,
     provider_service: ForgeProviderService,
+    provider_registry: ForgeProviderRegistry,
     conversation_service: ForgeConversationService,
     prompt_service: ForgeTemplateService,
     suggestion_service: Arc<ForgeSuggestionService<F>>,
+    checkpoint_service: ForgeCheckpointService,
 }
 
 impl<F: Infrastructure> ForgeApp<F> {
     pub fn new(infra: Arc<F>) -> Self {
+        let env = infra.environment_service().get_environment();
         let suggestion_service = Arc::new(ForgeSuggestionService::new(infra.clone()));
         Self {
             infra: infra.clone(),
             tool_service: ForgeToolService::new(infra.clone(), suggestion_service.clone()),
             provider_service: ForgeProviderService::new(infra.clone()),
-            conversation_service: ForgeConversationService::new(),
+            provider_registry: ForgeProviderRegistry::new(infra.clone()),
+            conversation_service: ForgeConversationService::persistent(env.conversation_path()),
             prompt_service: ForgeTemplateService::new(),
             suggestion_service,
+            checkpoint_service: ForgeCheckpointService::new(env.checkpoint_path()),
         }
     }
 }
@@ -41,9 +47,11 @@ impl<F: Infrastructure> ForgeApp<F> {
 impl<F: Infrastructure> App for ForgeApp<F> {
     type ToolService = ForgeToolService;
     type ProviderService = ForgeProviderService;
+    type ProviderRegistry = ForgeProviderRegistry;
     type ConversationService = ForgeConversationService;
     type PromptService = ForgeTemplateService;
     type SuggestionService = ForgeSuggestionService<F>;
+    type CheckpointService = ForgeCheckpointService;
 
     fn tool_service(&self) -> &Self::ToolService {
         &self.tool_service
@@ -57,6 +65,10 @@ impl<F: Infrastructure> App for ForgeApp<F> {
         &self.provider_service
     }
 
+    fn provider_registry(&self) -> &Self::ProviderRegistry {
+        &self.provider_registry
+    }
+
     fn conversation_service(&self) -> &Self::ConversationService {
         &self.conversation_service
     }
@@ -64,6 +76,10 @@ impl<F: Infrastructure> App for ForgeApp<F> {
     fn prompt_service(&self) -> &Self::PromptService {
         &self.prompt_service
     }
+
+    fn checkpoint_service(&self) -> &Self::CheckpointService {
+        &self.checkpoint_service
+    }
 }
 
 impl<F: Infrastructure> Infrastructure for ForgeApp<F> {
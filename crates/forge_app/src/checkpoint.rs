@@ -0,0 +1,239 @@
+//! Snapshots of files taken before they are modified by a tool.
+//!
+//! Checkpoints are stored per conversation and turn as
+//! `<conversation>/<turn>/manifest.json`, next to a copy of every file's
+//! previous content. Files that didn't exist before the turn are recorded
+//! without a copy and are removed on restore.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use forge_domain::{Checkpoint, CheckpointService, ConversationId};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+const MANIFEST: &str = "manifest.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    path: PathBuf,
+    /// Name of the file holding the previous content, if the file existed
+    blob: Option<String>,
+}
+
+pub struct ForgeCheckpointService {
+    path: PathBuf,
+    // Serializes updates to the manifests
+    lock: Mutex<()>,
+}
+
+impl ForgeCheckpointService {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), lock: Mutex::new(()) }
+    }
+
+    fn conversation_path(&self, id: &ConversationId) -> PathBuf {
+        self.path.join(id.into_string())
+    }
+
+    fn turn_path(&self, id: &ConversationId, turn: u64) -> PathBuf {
+        self.conversation_path(id).join(turn.to_string())
+    }
+
+    async fn manifest(&self, dir: &Path) -> anyhow::Result<Vec<Entry>> {
+        let path = dir.join(MANIFEST);
+        if !path.is_file() {
+            return Ok(Vec::new());
+        }
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read checkpoint: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse checkpoint: {}", path.display()))
+    }
+
+    /// Turns that have a checkpoint, in ascending order
+    async fn turns(&self, id: &ConversationId) -> anyhow::Result<Vec<u64>> {
+        let path = self.conversation_path(id);
+        if !path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut turns = Vec::new();
+        let mut entries = tokio::fs::read_dir(&path)
+            .await
+            .with_context(|| format!("Failed to read directory: {}", path.display()))?;
+        while let Some(entry) = entries.next_entry().await? {
+            if let Some(turn) = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.parse::<u64>().ok())
+            {
+                turns.push(turn);
+            }
+        }
+
+        turns.sort();
+        Ok(turns)
+    }
+}
+
+#[async_trait::async_trait]
+impl CheckpointService for ForgeCheckpointService {
+    async fn snapshot(&self, id: &ConversationId, turn: u64, path: &Path) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().await;
+        let dir = self.turn_path(id, turn);
+        let mut manifest = self.manifest(&dir).await?;
+        if manifest.iter().any(|entry| entry.path == path) {
+            return Ok(());
+        }
+
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+        let blob = if path.is_file() {
+            let blob = manifest.len().to_string();
+            tokio::fs::copy(path, dir.join(&blob))
+                .await
+                .with_context(|| format!("Failed to snapshot file: {}", path.display()))?;
+            Some(blob)
+        } else {
+            None
+        };
+
+        manifest.push(Entry { path: path.to_path_buf(), blob });
+        tokio::fs::write(dir.join(MANIFEST), serde_json::to_string(&manifest)?)
+            .await
+            .with_context(|| format!("Failed to write checkpoint: {}", dir.display()))?;
+        Ok(())
+    }
+
+    async fn list(&self, id: &ConversationId) -> anyhow::Result<Vec<Checkpoint>> {
+        let mut checkpoints = Vec::new();
+        for turn in self.turns(id).await? {
+            let files = self
+                .manifest(&self.turn_path(id, turn))
+                .await?
+                .into_iter()
+                .map(|entry| entry.path)
+                .collect();
+            checkpoints.push(Checkpoint { turn, files });
+        }
+        Ok(checkpoints)
+    }
+
+    async fn restore(&self, id: &ConversationId, turn: u64) -> anyhow::Result<Vec<PathBuf>> {
+        let _guard = self.lock.lock().await;
+        let mut restored = BTreeSet::new();
+
+        // Going from the latest turn backwards leaves every file with its
+        // content from before the earliest restored turn
+        for checkpoint in self.turns(id).await?.into_iter().rev() {
+            if checkpoint < turn {
+                break;
+            }
+
+            let dir = self.turn_path(id, checkpoint);
+            for entry in self.manifest(&dir).await? {
+                match entry.blob {
+                    Some(blob) => {
+                        if let Some(parent) = entry.path.parent() {
+                            tokio::fs::create_dir_all(parent).await.with_context(|| {
+                                format!("Failed to create directory: {}", parent.display())
+                            })?;
+                        }
+                        tokio::fs::copy(dir.join(blob), &entry.path)
+                            .await
+                            .with_context(|| {
+                                format!("Failed to restore file: {}", entry.path.display())
+                            })?;
+                    }
+                    None if entry.path.is_file() => {
+                        tokio::fs::remove_file(&entry.path).await.with_context(|| {
+                            format!("Failed to remove file: {}", entry.path.display())
+                        })?;
+                    }
+                    None => {}
+                }
+                restored.insert(entry.path);
+            }
+
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .with_context(|| format!("Failed to remove checkpoint: {}", dir.display()))?;
+        }
+
+        Ok(restored.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_restore_files_to_state_before_turn() {
+        let store = tempfile::tempdir().unwrap();
+        let workspace = tempfile::tempdir().unwrap();
+        let modified = workspace.path().join("modified.txt");
+        let created = workspace.path().join("created.txt");
+        let id = ConversationId::generate();
+        let service = ForgeCheckpointService::new(store.path());
+
+        tokio::fs::write(&modified, "original").await.unwrap();
+
+        // Turn 1 modifies the file twice, only the first snapshot is kept
+        service.snapshot(&id, 1, &modified).await.unwrap();
+        tokio::fs::write(&modified, "first").await.unwrap();
+        service.snapshot(&id, 1, &modified).await.unwrap();
+        tokio::fs::write(&modified, "second").await.unwrap();
+
+        // Turn 2 modifies the file again and creates a new one
+        service.snapshot(&id, 2, &modified).await.unwrap();
+        tokio::fs::write(&modified, "third").await.unwrap();
+        service.snapshot(&id, 2, &created).await.unwrap();
+        tokio::fs::write(&created, "new").await.unwrap();
+
+        let actual = service.list(&id).await.unwrap();
+        let expected = vec![
+            Checkpoint { turn: 1, files: vec![modified.clone()] },
+            Checkpoint { turn: 2, files: vec![modified.clone(), created.clone()] },
+        ];
+        assert_eq!(actual, expected);
+
+        let mut actual = service.restore(&id, 2).await.unwrap();
+        actual.sort();
+        let mut expected = vec![modified.clone(), created.clone()];
+        expected.sort();
+        assert_eq!(actual, expected);
+        assert_eq!(
+            tokio::fs::read_to_string(&modified).await.unwrap(),
+            "second"
+        );
+        assert!(!created.exists());
+
+        service.restore(&id, 1).await.unwrap();
+        assert_eq!(
+            tokio::fs::read_to_string(&modified).await.unwrap(),
+            "original"
+        );
+        assert!(service.list(&id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restore_without_checkpoints() {
+        let store = tempfile::tempdir().unwrap();
+        let service = ForgeCheckpointService::new(store.path());
+
+        let actual = service
+            .restore(&ConversationId::generate(), 1)
+            .await
+            .unwrap();
+        assert!(actual.is_empty());
+    }
+}
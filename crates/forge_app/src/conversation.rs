@@ -1,13 +1,21 @@
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use forge_domain::{
-    AgentId, Context, Conversation, ConversationId, ConversationService, Event, Workflow,
+    AgentId, Context, Conversation, ConversationId, ConversationService, Event, ToolName,
+    TurnSummary, Workflow,
 };
 use tokio::sync::Mutex;
 
+use crate::conversation_log::{ConversationLog, Record};
+
 pub struct ForgeConversationService {
     workflows: Arc<Mutex<HashMap<ConversationId, Conversation>>>,
+    /// Serializes the reads and writes of each conversation's log, so that
+    /// conversations don't wait on each other's disk I/O
+    io_locks: Arc<Mutex<HashMap<ConversationId, Arc<Mutex<()>>>>>,
+    log: Option<ConversationLog>,
 }
 
 impl Default for ForgeConversationService {
@@ -17,49 +25,436 @@ impl Default for ForgeConversationService {
 }
 
 impl ForgeConversationService {
+    /// Creates a service that keeps conversations in memory only
     pub fn new() -> Self {
-        Self { workflows: Arc::new(Mutex::new(HashMap::new())) }
+        Self {
+            workflows: Arc::new(Mutex::new(HashMap::new())),
+            io_locks: Arc::new(Mutex::new(HashMap::new())),
+            log: None,
+        }
+    }
+
+    /// Creates a service that additionally persists every change to an
+    /// append-only log inside the given directory, so that conversations
+    /// survive restarts and crashes.
+    pub fn persistent(path: impl Into<PathBuf>) -> Self {
+        Self {
+            workflows: Arc::new(Mutex::new(HashMap::new())),
+            io_locks: Arc::new(Mutex::new(HashMap::new())),
+            log: Some(ConversationLog::new(path)),
+        }
+    }
+
+    async fn io_lock(&self, id: &ConversationId) -> Arc<Mutex<()>> {
+        self.io_locks
+            .lock()
+            .await
+            .entry(id.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Loads the conversation into memory unless it's there already, and tells
+    /// whether it exists. Expects the caller to hold the conversation's I/O
+    /// lock.
+    async fn cache(&self, id: &ConversationId) -> anyhow::Result<bool> {
+        if self.workflows.lock().await.contains_key(id) {
+            return Ok(true);
+        }
+
+        match self.load(id).await? {
+            Some(conversation) => {
+                self.workflows.lock().await.insert(id.clone(), conversation);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Applies the change to the conversation and records it in the log.
+    /// Changes to unknown conversations are ignored.
+    async fn update(&self, id: &ConversationId, record: Record) -> anyhow::Result<bool> {
+        // The log is written without holding up other conversations
+        let io_lock = self.io_lock(id).await;
+        let _io = io_lock.lock().await;
+        if !self.cache(id).await? {
+            return Ok(false);
+        }
+
+        if let Some(log) = &self.log {
+            let delta = self
+                .workflows
+                .lock()
+                .await
+                .get(id)
+                .and_then(|conversation| record.delta(conversation));
+            log.append(id, delta.as_ref().unwrap_or(&record)).await?;
+        }
+        match self.workflows.lock().await.get_mut(id) {
+            Some(conversation) => {
+                record.apply(conversation);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn load(&self, id: &ConversationId) -> anyhow::Result<Option<Conversation>> {
+        match &self.log {
+            Some(log) => log.load(id).await,
+            None => Ok(None),
+        }
     }
 }
 
 #[async_trait::async_trait]
 impl ConversationService for ForgeConversationService {
     async fn get(&self, id: &ConversationId) -> anyhow::Result<Option<Conversation>> {
+        if let Some(conversation) = self.workflows.lock().await.get(id) {
+            return Ok(Some(conversation.clone()));
+        }
+
+        let io_lock = self.io_lock(id).await;
+        let _io = io_lock.lock().await;
+        if !self.cache(id).await? {
+            return Ok(None);
+        }
         Ok(self.workflows.lock().await.get(id).cloned())
     }
 
     async fn create(&self, workflow: Workflow) -> anyhow::Result<ConversationId> {
         let id = ConversationId::generate();
         let conversation = Conversation::new(id.clone(), workflow);
+        if let Some(log) = &self.log {
+            log.append(
+                &id,
+                &Record::Snapshot { conversation: conversation.clone() },
+            )
+            .await?;
+        }
         self.workflows.lock().await.insert(id.clone(), conversation);
         Ok(id)
     }
 
-    async fn inc_turn(&self, id: &ConversationId, agent: &AgentId) -> anyhow::Result<()> {
-        if let Some(c) = self.workflows.lock().await.get_mut(id) {
-            c.state.entry(agent.clone()).or_default().turn_count += 1;
+    async fn list(&self) -> anyhow::Result<Vec<Conversation>> {
+        let mut conversations = self
+            .workflows
+            .lock()
+            .await
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        // Listed logs aren't cached, since only loading them repairs and compacts
+        // them before further records are appended
+        if let Some(log) = &self.log {
+            for conversation in log.list().await? {
+                if !conversations
+                    .iter()
+                    .any(|cached| cached.id == conversation.id)
+                {
+                    conversations.push(conversation);
+                }
+            }
+        }
+
+        conversations.sort_by(|a, b| b.updated_at().cmp(&a.updated_at()));
+        Ok(conversations)
+    }
+
+    async fn upsert(&self, conversation: Conversation) -> anyhow::Result<()> {
+        let id = conversation.id.clone();
+        let io_lock = self.io_lock(&id).await;
+        let _io = io_lock.lock().await;
+        if let Some(log) = &self.log {
+            log.append(
+                &id,
+                &Record::Snapshot { conversation: conversation.clone() },
+            )
+            .await?;
         }
+        self.workflows.lock().await.insert(id, conversation);
         Ok(())
     }
+
+    async fn inc_turn(&self, id: &ConversationId, agent: &AgentId) -> anyhow::Result<()> {
+        self.update(id, Record::IncTurn { agent: agent.clone() })
+            .await?;
+        Ok(())
+    }
+
+    async fn allow_tool(&self, id: &ConversationId, tool: &ToolName) -> anyhow::Result<()> {
+        self.update(id, Record::AllowTool { tool: tool.clone() })
+            .await?;
+        Ok(())
+    }
+
+    async fn add_cost(&self, id: &ConversationId, cost: f64) -> anyhow::Result<()> {
+        self.update(id, Record::AddCost { cost }).await?;
+        Ok(())
+    }
+
+    async fn insert_summary(
+        &self,
+        id: &ConversationId,
+        key: String,
+        summary: TurnSummary,
+    ) -> anyhow::Result<()> {
+        self.update(id, Record::InsertSummary { key, summary })
+            .await?;
+        Ok(())
+    }
+
     async fn set_context(
         &self,
         id: &ConversationId,
         agent: &AgentId,
         context: Context,
     ) -> anyhow::Result<()> {
-        if let Some(c) = self.workflows.lock().await.get_mut(id) {
-            c.state.entry(agent.clone()).or_default().context = Some(context);
-        }
+        self.update(id, Record::SetContext { agent: agent.clone(), context })
+            .await?;
         Ok(())
     }
 
     async fn insert_event(&self, id: &ConversationId, event: Event) -> anyhow::Result<()> {
-        let mut guard = self.workflows.lock().await;
-        guard
-            .get_mut(id)
-            .ok_or_else(|| anyhow::anyhow!("Conversation not found"))?
-            .events
-            .push(event);
-        Ok(())
+        if self.update(id, Record::InsertEvent { event }).await? {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Conversation not found"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{ContextMessage, EventType, Image};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_persistent_conversation_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+        let context = Context::default().add_message(ContextMessage::user("Hello"));
+        let tool = ToolName::new("tool_forge_fs_create");
+        let summary = TurnSummary {
+            messages: context.messages.clone(),
+            summary: "Greeted".to_string(),
+        };
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let id = service.create(Workflow::default()).await.unwrap();
+        service.inc_turn(&id, &agent).await.unwrap();
+        service.allow_tool(&id, &tool).await.unwrap();
+        service.add_cost(&id, 0.25).await.unwrap();
+        service.add_cost(&id, 0.5).await.unwrap();
+        service
+            .insert_summary(&id, "key".to_string(), summary.clone())
+            .await
+            .unwrap();
+        service
+            .set_context(&id, &agent, context.clone())
+            .await
+            .unwrap();
+        service
+            .insert_event(&id, Event::task_init("Hello"))
+            .await
+            .unwrap();
+
+        let actual = ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(actual.turn_count(&agent), Some(1));
+        assert!(actual.allowed_tools.contains(&tool));
+        assert_eq!(actual.cost, 0.75);
+        assert_eq!(actual.summaries.get("key"), Some(&summary));
+        assert_eq!(actual.context(&agent), Some(&context));
+        assert_eq!(actual.events.len(), 1);
+        assert_eq!(actual.events[0].value(), "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_persistent_conversation_ignores_truncated_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let id = service.create(Workflow::default()).await.unwrap();
+        service.inc_turn(&id, &agent).await.unwrap();
+
+        // Simulate a crash in the middle of writing a record
+        let path = dir.path().join(format!("{}.jsonl", id.into_string()));
+        let mut content = tokio::fs::read_to_string(&path).await.unwrap();
+        content.push_str(r#"{"type":"inc_turn","age"#);
+        tokio::fs::write(&path, content).await.unwrap();
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let actual = service.get(&id).await.unwrap().unwrap();
+        assert_eq!(actual.turn_count(&agent), Some(1));
+
+        // The conversation can be continued after recovery
+        service.inc_turn(&id, &agent).await.unwrap();
+        let actual = ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.turn_count(&agent), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_log_is_compacted_past_max_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+        let lines = |id: &ConversationId| {
+            let path = dir.path().join(format!("{}.jsonl", id.into_string()));
+            std::fs::read_to_string(path).unwrap().lines().count()
+        };
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let id = service.create(Workflow::default()).await.unwrap();
+        service.inc_turn(&id, &agent).await.unwrap();
+        ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap();
+        assert_eq!(lines(&id), 2);
+
+        for _ in 0..100 {
+            service.inc_turn(&id, &agent).await.unwrap();
+        }
+        let actual = ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.turn_count(&agent), Some(101));
+        assert_eq!(lines(&id), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_persisted_conversations() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let first = service.create(Workflow::default()).await.unwrap();
+        let second = service.create(Workflow::default()).await.unwrap();
+        service
+            .insert_event(&first, Event::task_init("Hello"))
+            .await
+            .unwrap();
+        service
+            .insert_event(&second, Event::new(EventType::Title("Greeting".into())))
+            .await
+            .unwrap();
+
+        let actual = ForgeConversationService::persistent(dir.path())
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|conversation| (conversation.title(), conversation.id))
+            .collect::<Vec<_>>();
+
+        let expected = vec![(Some("Greeting".to_string()), second), (None, first)];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_context_images_are_logged_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+        let image = Image::new_bytes(b"screenshot", "image/png");
+        let context = Context::default().add_message(
+            ContextMessage::user("What's wrong here?").with_images(vec![image.clone()]),
+        );
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let id = service.create(Workflow::default()).await.unwrap();
+        service
+            .set_context(&id, &agent, context.clone())
+            .await
+            .unwrap();
+        let context = context.add_message(ContextMessage::assistant("A typo", None));
+        service
+            .set_context(&id, &agent, context.clone())
+            .await
+            .unwrap();
+
+        let path = dir.path().join(format!("{}.jsonl", id.into_string()));
+        let log = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(log.matches(image.data()).count(), 1);
+
+        let actual = ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.context(&agent), Some(&context));
+    }
+
+    #[tokio::test]
+    async fn test_list_leaves_logs_as_they_are() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+
+        let service = ForgeConversationService::persistent(dir.path());
+        let id = service.create(Workflow::default()).await.unwrap();
+        service.inc_turn(&id, &agent).await.unwrap();
+        let path = dir.path().join(format!("{}.jsonl", id.into_string()));
+        let expected = tokio::fs::read_to_string(&path).await.unwrap();
+
+        // A log that can't be read doesn't hide the others
+        let unreadable = format!("{}.jsonl", ConversationId::generate().into_string());
+        tokio::fs::create_dir(dir.path().join(unreadable))
+            .await
+            .unwrap();
+
+        let conversations = ForgeConversationService::persistent(dir.path())
+            .list()
+            .await
+            .unwrap();
+
+        let actual = tokio::fs::read_to_string(&path).await.unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0].turn_count(&agent), Some(1));
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_conversation_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentId::new("developer");
+        let id = ConversationId::generate();
+        let mut conversation = Conversation::new(id.clone(), Workflow::default());
+        conversation
+            .state
+            .entry(agent.clone())
+            .or_default()
+            .turn_count = 3;
+
+        ForgeConversationService::persistent(dir.path())
+            .upsert(conversation)
+            .await
+            .unwrap();
+
+        let actual = ForgeConversationService::persistent(dir.path())
+            .get(&id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(actual.turn_count(&agent), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_conversation_is_not_persisted() {
+        let service = ForgeConversationService::new();
+        let id = service.create(Workflow::default()).await.unwrap();
+
+        let actual = ForgeConversationService::new().get(&id).await.unwrap();
+        assert!(actual.is_none());
     }
 }
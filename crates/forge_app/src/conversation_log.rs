@@ -0,0 +1,282 @@
+//! Append-only, line delimited JSON log of conversation changes.
+//!
+//! Every conversation is stored in its own `<id>.jsonl` file. The first record
+//! is always a full snapshot of the conversation and every subsequent record
+//! describes a single change. Since each change is written as one line and
+//! flushed to disk before the call returns, a crash can at worst leave a
+//! partially written trailing line behind, which is ignored on replay.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use forge_domain::{
+    AgentId, Context, ContextMessage, Conversation, ConversationId, Event, ToolName, TurnSummary,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+
+/// Number of records after which a log is compacted when it's loaded
+const MAX_RECORDS: usize = 100;
+
+/// A single change made to a conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Record {
+    Snapshot {
+        conversation: Conversation,
+    },
+    IncTurn {
+        agent: AgentId,
+    },
+    SetContext {
+        agent: AgentId,
+        context: Context,
+    },
+    /// Messages added to the end of the agent's context
+    AddMessages {
+        agent: AgentId,
+        messages: Vec<ContextMessage>,
+    },
+    InsertEvent {
+        event: Event,
+    },
+    AllowTool {
+        tool: ToolName,
+    },
+    InsertSummary {
+        key: String,
+        summary: TurnSummary,
+    },
+    AddCost {
+        cost: f64,
+    },
+}
+
+impl Record {
+    /// Applies the change on top of the given conversation. Snapshots replace
+    /// the conversation entirely.
+    pub fn apply(self, conversation: &mut Conversation) {
+        match self {
+            Record::Snapshot { conversation: snapshot } => *conversation = snapshot,
+            Record::IncTurn { agent } => {
+                conversation.state.entry(agent).or_default().turn_count += 1;
+            }
+            Record::SetContext { agent, context } => {
+                conversation.state.entry(agent).or_default().context = Some(context);
+            }
+            Record::AddMessages { agent, messages } => {
+                let state = conversation.state.entry(agent).or_default();
+                state
+                    .context
+                    .get_or_insert_with(Context::default)
+                    .messages
+                    .extend(messages);
+            }
+            Record::InsertEvent { event } => conversation.events.push(event),
+            Record::AllowTool { tool } => {
+                conversation.allowed_tools.insert(tool);
+            }
+            Record::InsertSummary { key, summary } => {
+                conversation.summaries.insert(key, summary);
+            }
+            Record::AddCost { cost } => conversation.cost += cost,
+        }
+    }
+
+    /// A smaller record that makes the same change to the conversation, if
+    /// there is one. Contexts mostly just gain messages, so only those are
+    /// logged rather than the whole context with all of its images again.
+    pub fn delta(&self, conversation: &Conversation) -> Option<Record> {
+        let Record::SetContext { agent, context } = self else {
+            return None;
+        };
+        let current = conversation.state.get(agent)?.context.as_ref()?;
+        let unchanged = context.tools == current.tools
+            && context.tool_choice == current.tool_choice
+            && context.thinking == current.thinking
+            && context.output_schema == current.output_schema
+            && context.sampling == current.sampling;
+        if !unchanged {
+            return None;
+        }
+
+        let messages = context.messages.strip_prefix(current.messages.as_slice())?;
+        Some(Record::AddMessages { agent: agent.clone(), messages: messages.to_vec() })
+    }
+}
+
+pub struct ConversationLog {
+    path: PathBuf,
+}
+
+impl ConversationLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn file_path(&self, id: &ConversationId) -> PathBuf {
+        self.path.join(format!("{}.jsonl", id.into_string()))
+    }
+
+    /// Appends a record to the conversation's log and waits for it to reach
+    /// the disk.
+    pub async fn append(&self, id: &ConversationId, record: &Record) -> anyhow::Result<()> {
+        tokio::fs::create_dir_all(&self.path)
+            .await
+            .with_context(|| format!("Failed to create directory: {}", self.path.display()))?;
+
+        let path = self.file_path(id);
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("Failed to open conversation log: {}", path.display()))?;
+        file.write_all(line.as_bytes()).await?;
+        file.sync_data().await?;
+        Ok(())
+    }
+
+    /// Rebuilds the conversation by replaying its log. Long logs are compacted
+    /// into a single snapshot afterwards so that they don't grow indefinitely
+    /// across sessions.
+    pub async fn load(&self, id: &ConversationId) -> anyhow::Result<Option<Conversation>> {
+        let path = self.file_path(id);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read conversation log: {}", path.display()))?;
+
+        let (conversation, records) = replay(&path, &content);
+        let lines = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .count();
+        if let Some(ref conversation) = conversation {
+            // Also gets rid of corrupted trailing entries, so that new records
+            // aren't appended to a partially written line
+            let damaged = records < lines || !content.ends_with('\n');
+            if damaged || lines > MAX_RECORDS {
+                self.compact(id, conversation).await?;
+            }
+        }
+
+        Ok(conversation)
+    }
+
+    /// Loads every conversation that has a log in the directory. Unlike
+    /// [`Self::load`], the logs are left as they are, and logs that can't be
+    /// read are skipped.
+    pub async fn list(&self) -> anyhow::Result<Vec<Conversation>> {
+        if !self.path.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut conversations = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.path)
+            .await
+            .with_context(|| format!("Failed to read directory: {}", self.path.display()))?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                continue;
+            }
+
+            let is_conversation = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| ConversationId::parse(stem).is_ok());
+            if !is_conversation {
+                continue;
+            }
+
+            let content = match tokio::fs::read_to_string(&path).await {
+                Ok(content) => content,
+                Err(error) => {
+                    warn!(
+                        path = %path.display(),
+                        error = %error,
+                        "Skipping unreadable conversation log"
+                    );
+                    continue;
+                }
+            };
+            if let (Some(conversation), _) = replay(&path, &content) {
+                conversations.push(conversation);
+            }
+        }
+
+        Ok(conversations)
+    }
+
+    async fn compact(
+        &self,
+        id: &ConversationId,
+        conversation: &Conversation,
+    ) -> anyhow::Result<()> {
+        let path = self.file_path(id);
+        let temp = path.with_extension("jsonl.tmp");
+        let record = Record::Snapshot { conversation: conversation.clone() };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+
+        tokio::fs::write(&temp, line)
+            .await
+            .with_context(|| format!("Failed to write conversation log: {}", temp.display()))?;
+
+        // Renaming is atomic, so a crash leaves either the old or the new log
+        tokio::fs::rename(&temp, &path)
+            .await
+            .with_context(|| format!("Failed to compact conversation log: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Replays the records in the log until the end or the first corrupted entry,
+/// returning the conversation along with the number of records applied.
+fn replay(path: &Path, content: &str) -> (Option<Conversation>, usize) {
+    let mut conversation: Option<Conversation> = None;
+    let mut records = 0;
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let record = match serde_json::from_str::<Record>(line) {
+            Ok(record) => record,
+            Err(error) => {
+                // Anything after a corrupted line can't be trusted to apply cleanly
+                warn!(
+                    path = %path.display(),
+                    line = index + 1,
+                    error = %error,
+                    "Ignoring corrupted conversation log entry"
+                );
+                break;
+            }
+        };
+
+        match conversation.as_mut() {
+            Some(conversation) => record.apply(conversation),
+            None => match record {
+                Record::Snapshot { conversation: snapshot } => conversation = Some(snapshot),
+                _ => {
+                    warn!(path = %path.display(), "Conversation log doesn't start with a snapshot");
+                    break;
+                }
+            },
+        }
+        records += 1;
+    }
+
+    (conversation, records)
+}
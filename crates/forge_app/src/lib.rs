@@ -1,6 +1,9 @@
 mod app;
+mod checkpoint;
 mod conversation;
+mod conversation_log;
 mod provider;
+mod replay;
 mod suggestion;
 mod template;
 mod tool_service;
@@ -1,29 +1,45 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use forge_domain::{
-    ChatCompletionMessage, Context as ChatContext, Model, ModelId, Parameters, ProviderService,
-    ResultStream,
+    ChatCompletionMessage, Context as ChatContext, Model, ModelId, Parameters, ProviderConfig,
+    ProviderRegistry, ProviderService, ResultStream, RetryConfig,
 };
 use forge_open_router::ProviderBuilder;
 use moka2::future::Cache;
+use tokio::sync::Mutex;
 
+use crate::replay::Replayer;
 use crate::{EnvironmentService, Infrastructure};
 
 pub struct ForgeProviderService {
     or: Box<dyn ProviderService>,
     cache: Cache<ModelId, Parameters>,
+    // The prices of the models are looked up after every request
+    models: Cache<(), Vec<Model>>,
 }
 
 impl ForgeProviderService {
     pub fn new<F: Infrastructure>(infra: Arc<F>) -> Self {
         let env = infra.environment_service().get_environment();
+        let replayer = Replayer::from_env(&env);
         let or = ProviderBuilder::from_url(env.provider_url)
             .with_key(env.provider_key)
+            .with_retry_config(env.retry_config)
             .build()
             .expect("Failed to build provider");
 
-        Self { or, cache: Cache::new(1024) }
+        match replayer {
+            Some(replayer) => Self::from(replayer.wrap(or)),
+            None => Self::from(or),
+        }
+    }
+}
+
+impl From<Box<dyn ProviderService>> for ForgeProviderService {
+    fn from(or: Box<dyn ProviderService>) -> Self {
+        Self { or, cache: Cache::new(1024), models: Cache::new(1) }
     }
 }
 
@@ -38,7 +54,10 @@ impl ProviderService for ForgeProviderService {
     }
 
     async fn models(&self) -> Result<Vec<Model>> {
-        self.or.models().await
+        self.models
+            .try_get_with((), self.or.models())
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
     }
 
     async fn parameters(&self, model: &ModelId) -> anyhow::Result<Parameters> {
@@ -54,3 +73,93 @@ impl ProviderService for ForgeProviderService {
             .map_err(|e| anyhow::anyhow!(e))?)
     }
 }
+
+/// Creates the providers that are declared in workflows, once per
+/// configuration
+pub struct ForgeProviderRegistry {
+    retry_config: RetryConfig,
+    replayer: Option<Replayer>,
+    providers: Mutex<HashMap<ProviderConfig, Arc<dyn ProviderService>>>,
+}
+
+impl ForgeProviderRegistry {
+    pub fn new<F: Infrastructure>(infra: Arc<F>) -> Self {
+        let env = infra.environment_service().get_environment();
+        Self {
+            retry_config: env.retry_config.clone(),
+            replayer: Replayer::from_env(&env),
+            providers: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ProviderRegistry for ForgeProviderRegistry {
+    async fn get(&self, config: &ProviderConfig) -> Result<Arc<dyn ProviderService>> {
+        let mut providers = self.providers.lock().await;
+        if let Some(provider) = providers.get(config) {
+            return Ok(provider.clone());
+        }
+
+        let mut builder = ProviderBuilder::from_url(config.provider()?.to_base_url())
+            .with_retry_config(self.retry_config.clone());
+        if let Some(key_env) = &config.key_env {
+            let key = std::env::var(key_env)
+                .with_context(|| format!("Environment variable is not set: {}", key_env))?;
+            builder = builder.with_key(key);
+        }
+
+        let provider = match &self.replayer {
+            Some(replayer) => replayer.wrap(builder.build()?),
+            None => builder.build()?,
+        };
+        let provider: Arc<dyn ProviderService> = Arc::new(ForgeProviderService::from(provider));
+        providers.insert(config.clone(), provider.clone());
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::ProviderKind;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_registry_reuses_providers() {
+        let registry = ForgeProviderRegistry {
+            retry_config: RetryConfig::default(),
+            replayer: None,
+            providers: Mutex::new(HashMap::new()),
+        };
+        let config = ProviderConfig {
+            kind: ProviderKind::OpenAICompatible,
+            url: Some("http://localhost:11434/v1/".to_string()),
+            key_env: None,
+        };
+
+        let first = registry.get(&config).await.unwrap();
+        let second = registry.get(&config).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[tokio::test]
+    async fn test_registry_requires_key_env() {
+        let registry = ForgeProviderRegistry {
+            retry_config: RetryConfig::default(),
+            replayer: None,
+            providers: Mutex::new(HashMap::new()),
+        };
+        let config = ProviderConfig {
+            kind: ProviderKind::Anthropic,
+            url: None,
+            key_env: Some("FORGE_TEST_UNSET_PROVIDER_KEY".to_string()),
+        };
+
+        let error = registry.get(&config).await.err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Environment variable is not set: FORGE_TEST_UNSET_PROVIDER_KEY"
+        );
+    }
+}
@@ -0,0 +1,370 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use forge_domain::{
+    ChatCompletionMessage, Context as ChatContext, Environment, Model, ModelId, Parameters,
+    ProviderService, Replay, ReplayMode, ResultStream,
+};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio_stream::StreamExt;
+
+/// A recorded request along with the provider's response
+#[derive(Serialize, Deserialize)]
+struct Fixture<T> {
+    request: serde_json::Value,
+    response: T,
+}
+
+/// Wraps providers in a [`ReplayProvider`] when the environment asks for it
+#[derive(Clone)]
+pub struct Replayer {
+    replay: Replay,
+    /// Paths that differ between machines, along with their placeholders
+    placeholders: Vec<(String, &'static str)>,
+    /// Parts of the system prompt that differ between machines and workspaces
+    system_info: Regex,
+}
+
+/// Elements of the system prompt templates that hold the operating system, the
+/// shell and the files of the workspace
+const SYSTEM_INFO: &str =
+    r"<(operating_system|default_shell|file_list)>.*?</(operating_system|default_shell|file_list)>";
+
+impl Replayer {
+    pub fn from_env(env: &Environment) -> Option<Self> {
+        let replay = env.replay.clone()?;
+        let mut placeholders = vec![(env.cwd.display().to_string(), "<cwd>")];
+        if let Some(home) = &env.home {
+            placeholders.push((home.display().to_string(), "<home>"));
+        }
+        Some(Self::new(replay, placeholders))
+    }
+
+    fn new(replay: Replay, placeholders: Vec<(String, &'static str)>) -> Self {
+        let system_info = Regex::new(SYSTEM_INFO).expect("The pattern is valid");
+        Self { replay, placeholders, system_info }
+    }
+
+    pub fn wrap(&self, provider: Box<dyn ProviderService>) -> Box<dyn ProviderService> {
+        Box::new(ReplayProvider { inner: provider, replayer: self.clone() })
+    }
+
+    /// The request as JSON, without the parts that differ between machines
+    fn normalize(&self, request: impl Serialize) -> Result<String> {
+        let request = self.hide_paths(serde_json::to_string(&request)?);
+        Ok(self.system_info.replace_all(&request, "<$1/>").into_owned())
+    }
+
+    /// Replaces the paths of this machine with their placeholders
+    fn hide_paths(&self, mut json: String) -> String {
+        // Longer paths first, since the home directory usually contains the cwd
+        let mut placeholders = self.placeholders.clone();
+        placeholders.sort_by_key(|(path, _)| std::cmp::Reverse(path.len()));
+        for (path, placeholder) in placeholders.iter().filter(|(path, _)| !path.is_empty()) {
+            json = json.replace(path.as_str(), placeholder);
+        }
+        json
+    }
+
+    /// Replaces the placeholders with the paths of this machine
+    fn restore_paths(&self, mut json: String) -> String {
+        for (path, placeholder) in self
+            .placeholders
+            .iter()
+            .filter(|(path, _)| !path.is_empty())
+        {
+            json = json.replace(placeholder, path);
+        }
+        json
+    }
+
+    fn fixture_path(&self, kind: &str, request: &str) -> PathBuf {
+        let hash = format!("{:x}", Sha256::digest(request.as_bytes()));
+        self.replay
+            .path
+            .join(format!("{kind}-{}.json", &hash[..16]))
+    }
+
+    /// Replays the recorded response to the request, or records the response
+    /// of the provider
+    async fn fixture<T, F>(&self, kind: &str, request: impl Serialize, call: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T>>,
+    {
+        let request = self.normalize(request)?;
+        let path = self.fixture_path(kind, &request);
+        match self.replay.mode {
+            ReplayMode::Replay => {
+                let fixture = tokio::fs::read_to_string(&path).await.with_context(|| {
+                    format!(
+                        "No recorded response for the request, record it with FORGE_REPLAY=record: {}",
+                        path.display()
+                    )
+                })?;
+                // Recorded tool calls refer to the paths of this machine
+                let fixture: Fixture<T> = serde_json::from_str(&self.restore_paths(fixture))
+                    .with_context(|| format!("Failed to parse fixture: {}", path.display()))?;
+                Ok(fixture.response)
+            }
+            ReplayMode::Record => {
+                let response = call.await?;
+                let fixture = Fixture {
+                    request: serde_json::from_str(&request)?,
+                    response: serde_json::from_str::<serde_json::Value>(
+                        &self.hide_paths(serde_json::to_string(&response)?),
+                    )?,
+                };
+                write(&path, &fixture).await?;
+                Ok(response)
+            }
+        }
+    }
+}
+
+async fn write<T: Serialize>(path: &Path, fixture: &Fixture<T>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::write(path, serde_json::to_string_pretty(fixture)?)
+        .await
+        .with_context(|| format!("Failed to write fixture: {}", path.display()))
+}
+
+/// Records the responses of a provider to fixture files, or replays them
+/// without sending any request. Requests are matched by a hash of their
+/// normalized content.
+pub struct ReplayProvider {
+    inner: Box<dyn ProviderService>,
+    replayer: Replayer,
+}
+
+#[async_trait::async_trait]
+impl ProviderService for ReplayProvider {
+    async fn chat(
+        &self,
+        id: &ModelId,
+        context: ChatContext,
+    ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+        let request = serde_json::json!({ "model": id, "context": context });
+        let messages = self
+            .replayer
+            .fixture("chat", request, async {
+                self.inner
+                    .chat(id, context.clone())
+                    .await?
+                    // Retries aren't part of the response
+                    .filter(|message| !matches!(message, Ok(message) if message.retry.is_some()))
+                    .collect::<Result<Vec<_>>>()
+                    .await
+            })
+            .await?;
+
+        Ok(Box::pin(tokio_stream::iter(messages.into_iter().map(Ok))))
+    }
+
+    async fn models(&self) -> Result<Vec<Model>> {
+        self.replayer
+            .fixture("models", (), self.inner.models())
+            .await
+    }
+
+    async fn parameters(&self, model: &ModelId) -> Result<Parameters> {
+        self.replayer
+            .fixture("parameters", model, self.inner.parameters(model))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use forge_domain::{Content, ContextMessage, FinishReason};
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Answers every request with the same message
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl ProviderService for Echo {
+        async fn chat(
+            &self,
+            _id: &ModelId,
+            context: ChatContext,
+        ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+            let message = ChatCompletionMessage::default()
+                .content(Content::full(context.messages.len().to_string()))
+                .finish_reason(FinishReason::Stop);
+            Ok(Box::pin(tokio_stream::iter(vec![Ok(message)])))
+        }
+
+        async fn models(&self) -> Result<Vec<Model>> {
+            Ok(Vec::new())
+        }
+
+        async fn parameters(&self, _model: &ModelId) -> Result<Parameters> {
+            Ok(Parameters::new(true))
+        }
+    }
+
+    /// Answers every request with the path of a file
+    struct Read(&'static str);
+
+    #[async_trait::async_trait]
+    impl ProviderService for Read {
+        async fn chat(
+            &self,
+            _id: &ModelId,
+            _context: ChatContext,
+        ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+            let message = ChatCompletionMessage::default()
+                .content(Content::full(format!("Reading {}", self.0)))
+                .finish_reason(FinishReason::Stop);
+            Ok(Box::pin(tokio_stream::iter(vec![Ok(message)])))
+        }
+
+        async fn models(&self) -> Result<Vec<Model>> {
+            Ok(Vec::new())
+        }
+
+        async fn parameters(&self, _model: &ModelId) -> Result<Parameters> {
+            Ok(Parameters::new(true))
+        }
+    }
+
+    /// Fails every request, like a provider without network access
+    struct Offline;
+
+    #[async_trait::async_trait]
+    impl ProviderService for Offline {
+        async fn chat(
+            &self,
+            _id: &ModelId,
+            _context: ChatContext,
+        ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
+            Err(anyhow::anyhow!("offline"))
+        }
+
+        async fn models(&self) -> Result<Vec<Model>> {
+            Err(anyhow::anyhow!("offline"))
+        }
+
+        async fn parameters(&self, _model: &ModelId) -> Result<Parameters> {
+            Err(anyhow::anyhow!("offline"))
+        }
+    }
+
+    fn replayer(mode: ReplayMode, path: &Path, cwd: &str) -> Replayer {
+        Replayer::new(
+            Replay { mode, path: path.to_path_buf() },
+            vec![(cwd.to_string(), "<cwd>")],
+        )
+    }
+
+    async fn chat(provider: &dyn ProviderService, cwd: &str) -> Result<Vec<ChatCompletionMessage>> {
+        chat_on(provider, cwd, "linux", &["Cargo.toml"]).await
+    }
+
+    async fn chat_on(
+        provider: &dyn ProviderService,
+        cwd: &str,
+        os: &str,
+        files: &[&str],
+    ) -> Result<Vec<ChatCompletionMessage>> {
+        let files = files
+            .iter()
+            .map(|file| format!(" - {file}\n"))
+            .collect::<String>();
+        let context = ChatContext::default()
+            .add_message(ContextMessage::system(format!(
+                "<operating_system>{os}</operating_system>\n<current_working_directory>{cwd}</current_working_directory>\n<file_list>\n{files}</file_list>"
+            )))
+            .add_message(ContextMessage::user("Hello"));
+        provider
+            .chat(&ModelId::new("gpt-4o"), context)
+            .await?
+            .collect::<Result<Vec<_>>>()
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_response() {
+        let fixtures = tempfile::tempdir().unwrap();
+        let recorder =
+            replayer(ReplayMode::Record, fixtures.path(), "/home/me/project").wrap(Box::new(Echo));
+        let expected = chat(recorder.as_ref(), "/home/me/project").await.unwrap();
+
+        // Another machine replays the fixture without network access
+        let replayer =
+            replayer(ReplayMode::Replay, fixtures.path(), "/ci/project").wrap(Box::new(Offline));
+        let actual = chat(replayer.as_ref(), "/ci/project").await.unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_replay_on_another_system() {
+        let fixtures = tempfile::tempdir().unwrap();
+        let recorder =
+            replayer(ReplayMode::Record, fixtures.path(), "/home/me/project").wrap(Box::new(Echo));
+        let expected = chat_on(
+            recorder.as_ref(),
+            "/home/me/project",
+            "macos",
+            &["Cargo.toml"],
+        )
+        .await
+        .unwrap();
+
+        // The workspace gained a file since the recording
+        let replayer =
+            replayer(ReplayMode::Replay, fixtures.path(), "/ci/project").wrap(Box::new(Offline));
+        let actual = chat_on(
+            replayer.as_ref(),
+            "/ci/project",
+            "linux",
+            &["Cargo.toml", "README.md"],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_replay_restores_paths() {
+        let fixtures = tempfile::tempdir().unwrap();
+        let recorder = replayer(ReplayMode::Record, fixtures.path(), "/home/me/project")
+            .wrap(Box::new(Read("/home/me/project/README.md")));
+        chat(recorder.as_ref(), "/home/me/project").await.unwrap();
+
+        let replayer =
+            replayer(ReplayMode::Replay, fixtures.path(), "/ci/project").wrap(Box::new(Offline));
+        let actual = chat(replayer.as_ref(), "/ci/project").await.unwrap();
+
+        let expected = vec![ChatCompletionMessage::default()
+            .content(Content::full("Reading /ci/project/README.md"))
+            .finish_reason(FinishReason::Stop)];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_replay_unknown_request() {
+        let fixtures = tempfile::tempdir().unwrap();
+        let replayer =
+            replayer(ReplayMode::Replay, fixtures.path(), "/ci/project").wrap(Box::new(Offline));
+
+        let actual = chat(replayer.as_ref(), "/ci/project").await.unwrap_err();
+        assert!(
+            actual
+                .to_string()
+                .starts_with("No recorded response for the request"),
+            "{actual}"
+        );
+    }
+}
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use forge_domain::{
-    SuggestionService, Tool, ToolCallFull, ToolDefinition, ToolName, ToolResult, ToolService,
+    CancellationToken, SuggestionService, Tool, ToolCallFull, ToolDefinition, ToolName, ToolResult,
+    ToolService,
 };
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error};
@@ -35,7 +36,7 @@ impl FromIterator<Tool> for ForgeToolService {
 
 #[async_trait::async_trait]
 impl ToolService for ForgeToolService {
-    async fn call(&self, call: ToolCallFull) -> ToolResult {
+    async fn call(&self, call: ToolCallFull, cancel: CancellationToken) -> ToolResult {
         let name = call.name.clone();
         let input = call.arguments.clone();
         debug!(tool_name = ?call.name, arguments = ?call.arguments, "Executing tool call");
@@ -49,7 +50,12 @@ impl ToolService for ForgeToolService {
         let output = match self.tools.get(&name) {
             Some(tool) => {
                 // Wrap tool call with timeout
-                match timeout(TOOL_CALL_TIMEOUT, tool.executable.call(input)).await {
+                match timeout(
+                    TOOL_CALL_TIMEOUT,
+                    tool.executable.call_cancellable(input, cancel),
+                )
+                .await
+                {
                     Ok(result) => result,
                     Err(_) => Err(anyhow::anyhow!(
                         "Tool '{}' timed out after {} minutes",
@@ -110,7 +116,7 @@ impl ToolService for ForgeToolService {
 #[cfg(test)]
 mod test {
     use anyhow::bail;
-    use forge_domain::{Tool, ToolCallId, ToolDefinition};
+    use forge_domain::{Tool, ToolCallId, ToolDefinition, ToolKind};
     use serde_json::{json, Value};
     use tokio::time;
 
@@ -145,6 +151,7 @@ mod test {
                 description: "A test tool that always succeeds".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                kind: ToolKind::Read,
             },
             executable: Box::new(SuccessTool),
         };
@@ -155,6 +162,7 @@ mod test {
                 description: "A test tool that always fails".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                kind: ToolKind::Read,
             },
             executable: Box::new(FailureTool),
         };
@@ -171,7 +179,7 @@ mod test {
             call_id: Some(ToolCallId::new("test")),
         };
 
-        let result = service.call(call).await;
+        let result = service.call(call, CancellationToken::new()).await;
         insta::assert_snapshot!(result);
     }
 
@@ -184,7 +192,7 @@ mod test {
             call_id: Some(ToolCallId::new("test")),
         };
 
-        let result = service.call(call).await;
+        let result = service.call(call, CancellationToken::new()).await;
         insta::assert_snapshot!(result);
     }
 
@@ -197,7 +205,7 @@ mod test {
             call_id: Some(ToolCallId::new("test")),
         };
 
-        let result = service.call(call).await;
+        let result = service.call(call, CancellationToken::new()).await;
         insta::assert_snapshot!(result);
     }
 
@@ -224,6 +232,7 @@ mod test {
                 description: "A test tool that takes too long".to_string(),
                 input_schema: schemars::schema_for!(serde_json::Value),
                 output_schema: Some(schemars::schema_for!(String)),
+                kind: ToolKind::Read,
             },
             executable: Box::new(SlowTool),
         };
@@ -238,7 +247,7 @@ mod test {
         // Advance time to trigger timeout
         test::time::advance(Duration::from_secs(305)).await;
 
-        let result = service.call(call).await;
+        let result = service.call(call, CancellationToken::new()).await;
 
         // Assert that the result contains a timeout error message
         let content_str = &result.content;
@@ -248,4 +257,39 @@ mod test {
         );
         assert!(result.is_error, "Expected error result for timeout");
     }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_tool_cancelled() {
+        test::time::pause();
+
+        let slow_tool = Tool {
+            definition: ToolDefinition {
+                name: ToolName::new("slow_tool"),
+                description: "A test tool that takes too long".to_string(),
+                input_schema: schemars::schema_for!(serde_json::Value),
+                output_schema: Some(schemars::schema_for!(String)),
+                kind: ToolKind::Read,
+            },
+            executable: Box::new(SlowTool),
+        };
+
+        let service = ForgeToolService::from_iter(vec![slow_tool]);
+        let call = ToolCallFull {
+            name: ToolName::new("slow_tool"),
+            arguments: json!("test input"),
+            call_id: Some(ToolCallId::new("test")),
+        };
+
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            canceller.cancel();
+        });
+
+        let result = service.call(call, cancel).await;
+
+        assert!(result.content.contains("Cancelled by user"));
+        assert!(result.is_error, "Expected error result for cancellation");
+    }
 }
@@ -20,6 +20,10 @@ impl NamedTool for Fetch {
     fn tool_name() -> forge_domain::ToolName {
         forge_domain::ToolName::new("tool_forge_net_fetch")
     }
+
+    fn tool_kind() -> forge_domain::ToolKind {
+        forge_domain::ToolKind::Network
+    }
 }
 
 impl Default for Fetch {
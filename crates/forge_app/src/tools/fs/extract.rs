@@ -0,0 +1,325 @@
+//! Extracts the text of documents, so that they can be read like text files.
+//!
+//! The format is detected from the content of the file rather than its
+//! extension: PDF files by their header, and DOCX, ODT, XLSX and ODS files by
+//! the entries of their ZIP archive.
+
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, Result};
+use calamine::Reader;
+use quick_xml::events::Event;
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// Returns the content of a text file, or the text extracted from a document
+pub fn text(bytes: Vec<u8>) -> Result<String> {
+    if is_pdf(&bytes) {
+        let pdf = pdf_extract::extract_text_from_mem(&bytes)
+            .context("Failed to extract the text of the PDF file");
+        // A text file that happens to start like a PDF is still text
+        return match (pdf, std::str::from_utf8(&bytes)) {
+            (Ok(text), _) => Ok(text),
+            (Err(_), Ok(text)) => Ok(text.to_string()),
+            (Err(error), Err(_)) => Err(error),
+        };
+    }
+    if bytes.starts_with(ZIP_MAGIC) {
+        return archive_text(&bytes);
+    }
+
+    String::from_utf8(bytes).map_err(|error| {
+        match infer::get(error.as_bytes()) {
+            Some(kind) => anyhow::anyhow!(
+                "Cannot read binary file of type {}, only text files, PDF, DOCX, ODT, XLSX and ODS documents are supported",
+                kind.mime_type()
+            ),
+            None => anyhow::anyhow!(
+                "Cannot read binary file, only text files, PDF, DOCX, ODT, XLSX and ODS documents are supported"
+            ),
+        }
+    })
+}
+
+/// PDF files start with their header, after an optional byte order mark and
+/// whitespace
+fn is_pdf(bytes: &[u8]) -> bool {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    bytes[start..].starts_with(PDF_MAGIC)
+}
+
+/// Office documents are ZIP archives, which are told apart by their entries
+fn archive_text(bytes: &[u8]) -> Result<String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to open the ZIP archive")?;
+
+    if archive.by_name("word/document.xml").is_ok() {
+        let xml = entry(&mut archive, "word/document.xml")?;
+        return xml_text(&xml, &DOCX);
+    }
+    if archive.by_name("xl/workbook.xml").is_ok() {
+        return spreadsheet_text(bytes);
+    }
+
+    let mime_type = entry(&mut archive, "mimetype").unwrap_or_default();
+    match mime_type.trim() {
+        "application/vnd.oasis.opendocument.text" => {
+            let xml = entry(&mut archive, "content.xml")?;
+            xml_text(&xml, &ODT)
+        }
+        "application/vnd.oasis.opendocument.spreadsheet" => spreadsheet_text(bytes),
+        _ => anyhow::bail!(
+            "Cannot read ZIP archive, only DOCX, ODT, XLSX and ODS documents are supported"
+        ),
+    }
+}
+
+fn entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
+    let mut content = String::new();
+    archive
+        .by_name(name)?
+        .read_to_string(&mut content)
+        .with_context(|| format!("Failed to read {name} from the document"))?;
+    Ok(content)
+}
+
+/// Elements of a document's XML that structure its text
+struct Markup {
+    /// Elements that hold the text, whitespace elsewhere only formats the XML
+    text: &'static [&'static [u8]],
+    /// Elements that end with a line break
+    paragraphs: &'static [&'static [u8]],
+    tab: &'static [u8],
+    line_break: &'static [u8],
+    /// Element that stands for a number of spaces, given by its `count`
+    /// attribute
+    spaces: Option<(&'static [u8], &'static [u8])>,
+}
+
+const DOCX: Markup = Markup {
+    text: &[b"w:t"],
+    paragraphs: &[b"w:p"],
+    tab: b"w:tab",
+    line_break: b"w:br",
+    spaces: None,
+};
+
+const ODT: Markup = Markup {
+    text: &[b"text:p", b"text:h"],
+    paragraphs: &[b"text:p", b"text:h"],
+    tab: b"text:tab",
+    line_break: b"text:line-break",
+    spaces: Some((b"text:s", b"text:c")),
+};
+
+fn xml_text(xml: &str, markup: &Markup) -> Result<String> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut output = String::new();
+    let mut depth = 0usize;
+    loop {
+        match reader
+            .read_event()
+            .context("Failed to parse the document")?
+        {
+            Event::Start(element) if markup.text.contains(&element.name().as_ref()) => depth += 1,
+            Event::End(element) => {
+                let name = element.name();
+                if markup.text.contains(&name.as_ref()) {
+                    depth = depth.saturating_sub(1);
+                }
+                if markup.paragraphs.contains(&name.as_ref()) {
+                    output.push('\n');
+                }
+            }
+            Event::Start(element) | Event::Empty(element) => {
+                let name = element.name();
+                if name.as_ref() == markup.tab {
+                    output.push('\t');
+                } else if name.as_ref() == markup.line_break {
+                    output.push('\n');
+                } else if let Some((spaces, count)) = markup.spaces {
+                    if name.as_ref() == spaces {
+                        let count = element
+                            .try_get_attribute(count)?
+                            .and_then(|count| count.unescape_value().ok()?.parse().ok())
+                            .unwrap_or(1);
+                        output.push_str(&" ".repeat(count));
+                    }
+                }
+            }
+            Event::Text(text) if depth > 0 => output.push_str(&text.unescape()?),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(output)
+}
+
+/// Every sheet of the workbook as CSV, below a line with its name
+fn spreadsheet_text(bytes: &[u8]) -> Result<String> {
+    let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(bytes))
+        .context("Failed to open the spreadsheet")?;
+
+    let mut output = String::new();
+    for (name, range) in workbook.worksheets() {
+        output.push_str(&format!("# Sheet: {name}\n"));
+        for row in range.rows() {
+            let cells = row
+                .iter()
+                .map(|cell| csv_field(&cell.to_string()))
+                .collect::<Vec<_>>();
+            output.push_str(&cells.join(","));
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use pretty_assertions::assert_eq;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    fn archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_text_file() {
+        let actual = text(b"fn main() {}\n".to_vec()).unwrap();
+        assert_eq!(actual, "fn main() {}\n");
+    }
+
+    #[test]
+    fn test_text_file_mentioning_pdf() {
+        let content = "const PDF_MAGIC: &[u8] = b\"%PDF-\";\n";
+        let actual = text(content.as_bytes().to_vec()).unwrap();
+        assert_eq!(actual, content);
+    }
+
+    #[test]
+    fn test_text_file_starting_like_pdf() {
+        let content = "%PDF-1.7 is the version that we target\n";
+        let actual = text(content.as_bytes().to_vec()).unwrap();
+        assert_eq!(actual, content);
+    }
+
+    #[test]
+    fn test_docx() {
+        let document = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:body>
+    <w:p><w:r><w:t>Release notes</w:t></w:r></w:p>
+    <w:p><w:r><w:t xml:space="preserve">Fixed </w:t></w:r><w:r><w:t>login &amp; logout</w:t><w:tab/><w:t>#42</w:t></w:r></w:p>
+  </w:body>
+</w:document>"#;
+        let bytes = archive(&[
+            ("[Content_Types].xml", "<Types/>"),
+            ("word/document.xml", document),
+        ]);
+
+        let actual = text(bytes).unwrap();
+        assert_eq!(actual, "Release notes\nFixed login & logout\t#42\n");
+    }
+
+    #[test]
+    fn test_odt() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+  <office:body><office:text>
+    <text:h>Agenda</text:h>
+    <text:p>Item<text:s text:c="2"/>one<text:line-break/>Item two</text:p>
+  </office:text></office:body>
+</office:document-content>"#;
+        let bytes = archive(&[
+            ("mimetype", "application/vnd.oasis.opendocument.text"),
+            ("content.xml", content),
+        ]);
+
+        let actual = text(bytes).unwrap();
+        assert_eq!(actual, "Agenda\nItem  one\nItem two\n");
+    }
+
+    #[test]
+    fn test_xlsx() {
+        let workbook = r#"<?xml version="1.0" encoding="UTF-8"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+  <sheets><sheet name="Budget" sheetId="1" r:id="rId1"/></sheets>
+</workbook>"#;
+        let relationships = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>
+</Relationships>"#;
+        let sheet = r#"<?xml version="1.0" encoding="UTF-8"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+  <sheetData>
+    <row r="1"><c r="A1" t="inlineStr"><is><t>Item</t></is></c><c r="B1" t="inlineStr"><is><t>Cost</t></is></c></row>
+    <row r="2"><c r="A2" t="inlineStr"><is><t>Rent, office</t></is></c><c r="B2"><v>1200</v></c></row>
+  </sheetData>
+</worksheet>"#;
+        let bytes = archive(&[
+            ("xl/workbook.xml", workbook),
+            ("xl/_rels/workbook.xml.rels", relationships),
+            ("xl/worksheets/sheet1.xml", sheet),
+        ]);
+
+        let actual = text(bytes).unwrap();
+        assert_eq!(
+            actual,
+            "# Sheet: Budget\nItem,Cost\n\"Rent, office\",1200\n"
+        );
+    }
+
+    #[test]
+    fn test_other_archive() {
+        let bytes = archive(&[("src/main.rs", "fn main() {}")]);
+
+        let actual = text(bytes).unwrap_err();
+        assert_eq!(
+            actual.to_string(),
+            "Cannot read ZIP archive, only DOCX, ODT, XLSX and ODS documents are supported"
+        );
+    }
+
+    #[test]
+    fn test_binary_file() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\xff\xfe".to_vec();
+
+        let actual = text(png).unwrap_err();
+        assert_eq!(
+            actual.to_string(),
+            "Cannot read binary file of type image/png, only text files, PDF, DOCX, ODT, XLSX and ODS documents are supported"
+        );
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use anyhow::Context;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -26,6 +26,10 @@ impl NamedTool for FSFileInfo {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_info")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
 }
 
 #[async_trait::async_trait]
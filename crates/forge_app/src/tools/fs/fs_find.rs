@@ -1,18 +1,26 @@
-use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use anyhow::Context;
 use forge_display::{GrepFormat, Kind, TitleFormat};
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
-use forge_walker::Walker;
-use regex::Regex;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{
+    BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
+};
+use ignore::overrides::OverrideBuilder;
+use ignore::{WalkBuilder, WalkState};
+use regex::{Regex, RegexBuilder};
 use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::tools::utils::assert_absolute_path;
 
-#[derive(Deserialize, JsonSchema)]
+/// Number of matches that are returned when the input doesn't set a limit
+const MAX_RESULTS: usize = 200;
+
+#[derive(Default, Deserialize, JsonSchema)]
 pub struct FSSearchInput {
     /// The path of the directory to search in (absolute path required). This
     /// directory will be recursively searched.
@@ -22,12 +30,24 @@ pub struct FSSearchInput {
     /// Glob pattern to filter files (e.g., '*.ts' for TypeScript files). If not
     /// provided, it will search all files (*).
     pub file_pattern: Option<String>,
+    /// Match case sensitively. Defaults to false.
+    pub case_sensitive: Option<bool>,
+    /// Treat the pattern as a literal string. Defaults to false.
+    pub literal: Option<bool>,
+    /// Lines of context before each match.
+    pub before_context: Option<usize>,
+    /// Lines of context after each match.
+    pub after_context: Option<usize>,
+    /// Maximum number of matches. Defaults to 200.
+    pub max_results: Option<usize>,
+    /// Let matches span lines, with `.` matching line breaks.
+    pub multiline: Option<bool>,
 }
 
 /// Request to perform a regex search on the content across files in a specified
-/// directory, providing context-rich results. This tool searches for patterns
-/// or specific content across multiple files, displaying each match with
-/// encapsulating context. The path must be absolute.
+/// directory, skipping binary and gitignored files. The path must be absolute.
+/// Matches are shown as `path:line:content` and context lines as
+/// `path-line-content`. The output says when results were truncated.
 #[derive(ToolDescription)]
 pub struct FSSearch;
 
@@ -48,96 +68,215 @@ impl NamedTool for FSSearch {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_search")
     }
-}
 
-#[async_trait::async_trait]
-impl ExecutableTool for FSSearch {
-    type Input = FSSearchInput;
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
+}
 
-    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
-        let dir = Path::new(&input.path);
-        assert_absolute_path(dir)?;
+impl FSSearchInput {
+    fn pattern(&self) -> String {
+        if self.literal.unwrap_or_default() {
+            regex::escape(&self.regex)
+        } else {
+            self.regex.clone()
+        }
+    }
 
-        if !dir.exists() {
-            return Err(anyhow::anyhow!("Directory '{}' does not exist", input.path));
+    fn matcher(&self) -> anyhow::Result<RegexMatcher> {
+        let multiline = self.multiline.unwrap_or_default();
+        let mut builder = RegexMatcherBuilder::new();
+        builder
+            .case_insensitive(!self.case_sensitive.unwrap_or_default())
+            .multi_line(true)
+            .dot_matches_new_line(multiline);
+        // Matches can't span lines unless requested, which is much faster
+        if !multiline {
+            builder.line_terminator(Some(b'\n'));
         }
+        builder
+            .build(&self.pattern())
+            .with_context(|| format!("Invalid regex pattern: {}", self.regex))
+    }
 
-        // Create regex pattern - case-insensitive by default
-        let pattern = format!("(?i){}", input.regex);
-        let regex = Regex::new(&pattern)
-            .with_context(|| format!("Invalid regex pattern: {}", input.regex))?;
+    /// The regex to highlight matches in the terminal
+    fn highlight(&self) -> anyhow::Result<Regex> {
+        RegexBuilder::new(&self.pattern())
+            .case_insensitive(!self.case_sensitive.unwrap_or_default())
+            .build()
+            .with_context(|| format!("Invalid regex pattern: {}", self.regex))
+    }
 
-        // TODO: Current implementation is extremely slow and inefficient.
-        // It should ideally be taking in a stream of files and processing them
-        // concurrently.
-        let walker = Walker::max_all().cwd(dir.to_path_buf());
+    fn searcher(&self) -> Searcher {
+        SearcherBuilder::new()
+            .line_number(true)
+            .multi_line(self.multiline.unwrap_or_default())
+            .before_context(self.before_context.unwrap_or_default())
+            .after_context(self.after_context.unwrap_or_default())
+            .binary_detection(BinaryDetection::quit(b'\x00'))
+            .build()
+    }
+}
 
-        let files = walker
-            .get()
-            .await
-            .with_context(|| format!("Failed to walk directory '{}'", dir.display()))?;
+/// The lines of a single match in ripgrep's format, along with its context
+type Match = Vec<String>;
 
-        let mut matches = Vec::new();
-        let mut seen_paths = HashSet::new();
+/// Collects the matches of a file, each with the context around it
+struct FileSink<'a> {
+    path: &'a Path,
+    max_results: usize,
+    /// Context before the next match and the breaks between groups of lines
+    pending: Vec<String>,
+    matches: Vec<Match>,
+}
 
-        for file in files {
-            if file.is_dir() {
-                continue;
-            }
+impl FileSink<'_> {
+    fn line(&self, separator: char, line_number: Option<u64>, bytes: &[u8]) -> String {
+        let content = String::from_utf8_lossy(bytes);
+        let content = content.trim_end_matches(['\r', '\n']);
+        format!(
+            "{}{separator}{}{separator}{content}",
+            self.path.display(),
+            line_number.unwrap_or_default()
+        )
+    }
+}
 
-            let path = Path::new(&file.path);
-            let full_path = dir.join(path);
-
-            // Apply file pattern filter if provided
-            if let Some(ref pattern) = input.file_pattern {
-                let glob = glob::Pattern::new(pattern).with_context(|| {
-                    format!(
-                        "Invalid glob pattern '{}' for file '{}'",
-                        pattern,
-                        full_path.display(),
-                    )
-                })?;
-                if let Some(filename) = path.file_name().unwrap_or(path.as_os_str()).to_str() {
-                    if !glob.matches(filename) {
-                        continue;
-                    }
-                }
-            }
+impl Sink for FileSink<'_> {
+    type Error = std::io::Error;
 
-            // Skip if we've already processed this file
-            if !seen_paths.insert(full_path.clone()) {
-                continue;
-            }
+    fn matched(&mut self, _: &Searcher, mat: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        let mut lines = std::mem::take(&mut self.pending);
+        let first = mat.line_number();
+        for (offset, line) in mat.lines().enumerate() {
+            lines.push(self.line(':', first.map(|first| first + offset as u64), line));
+        }
+        self.matches.push(lines);
+        // One match past the limit shows that the results are truncated
+        Ok(self.matches.len() <= self.max_results)
+    }
 
-            // Try to read the file content
-            let content = match tokio::fs::read_to_string(&full_path).await {
-                Ok(content) => content,
-                Err(e) => {
-                    // Skip binary or unreadable files silently
-                    if e.kind() != std::io::ErrorKind::InvalidData {
-                        matches.push(format!("Error reading {:?}: {}", full_path.display(), e));
-                    }
-                    continue;
-                }
+    fn context(&mut self, _: &Searcher, context: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        let line = self.line('-', context.line_number(), context.bytes());
+        match (context.kind(), self.matches.last_mut()) {
+            (SinkContextKind::After, Some(lines)) => lines.push(line),
+            _ => self.pending.push(line),
+        }
+        Ok(true)
+    }
+
+    fn context_break(&mut self, _: &Searcher) -> Result<bool, Self::Error> {
+        self.pending.push("--".to_string());
+        Ok(true)
+    }
+}
+
+/// Searches the files below `dir` on all cores, returning the first matching
+/// lines in path order and whether there were more matches
+fn search(
+    dir: &Path,
+    input: &FSSearchInput,
+    matcher: RegexMatcher,
+) -> anyhow::Result<(Vec<String>, bool)> {
+    let mut walker = WalkBuilder::new(dir);
+    walker.require_git(false);
+    if let Some(pattern) = &input.file_pattern {
+        let overrides = OverrideBuilder::new(dir)
+            .add(pattern)
+            .and_then(|builder| builder.build())
+            .with_context(|| format!("Invalid glob pattern '{pattern}'"))?;
+        walker.overrides(overrides);
+    }
+
+    let max_results = input.max_results.unwrap_or(MAX_RESULTS);
+    let files = Mutex::new(Vec::<(PathBuf, Vec<Match>)>::new());
+    walker.build_parallel().run(|| {
+        let matcher = matcher.clone();
+        let mut searcher = input.searcher();
+        let files = &files;
+        Box::new(move |entry| {
+            // Unreadable entries are skipped like binary files
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
             };
+            if !entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+            {
+                return WalkState::Continue;
+            }
 
-            // Process the file line by line
-            for (line_num, line) in content.lines().enumerate() {
-                if regex.is_match(line) {
-                    // Format match in ripgrep style: filepath:line_num:content
-                    matches.push(format!("{}:{}:{}", full_path.display(), line_num + 1, line));
+            let mut sink = FileSink {
+                path: entry.path(),
+                max_results,
+                pending: Vec::new(),
+                matches: Vec::new(),
+            };
+            if searcher
+                .search_path(&matcher, entry.path(), &mut sink)
+                .is_ok()
+                && !sink.matches.is_empty()
+            {
+                let matches = sink.matches;
+                if let Ok(mut files) = files.lock() {
+                    files.push((entry.path().to_path_buf(), matches));
                 }
             }
+            WalkState::Continue
+        })
+    });
+
+    // The files are searched in any order, so the limit is applied once they
+    // are sorted
+    let mut files = files
+        .into_inner()
+        .map_err(|_| anyhow::anyhow!("Search thread panicked"))?;
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let mut matches = files.into_iter().flat_map(|(_, matches)| matches);
+    let lines = matches.by_ref().take(max_results).flatten().collect();
+    Ok((lines, matches.next().is_some()))
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for FSSearch {
+    type Input = FSSearchInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let dir = Path::new(&input.path);
+        assert_absolute_path(dir)?;
+
+        if !dir.exists() {
+            return Err(anyhow::anyhow!("Directory '{}' does not exist", input.path));
+        }
+        if input.max_results == Some(0) {
+            anyhow::bail!("max_results has to be at least 1");
         }
 
+        let matcher = input.matcher()?;
+        let highlight = input.highlight()?;
+
+        let dir = dir.to_path_buf();
+        let (input, (matches, truncated)) = tokio::task::spawn_blocking(move || {
+            let results = search(&dir, &input, matcher);
+            results.map(|results| (input, results))
+        })
+        .await??;
+
         // Print title
         println!("{}", TitleFormat::from(&input).format());
 
         // Print results using GrepFormat for all cases
-        let formatted_output = GrepFormat::new(matches.clone()).format(&regex);
+        let formatted_output = GrepFormat::new(matches.clone()).format(&highlight);
         println!("{}", formatted_output);
 
-        Ok(matches.join("\n"))
+        let mut output = matches.join("\n");
+        if truncated {
+            output.push_str(&format!(
+                "\n... results truncated after {} matches, narrow the search or raise max_results",
+                input.max_results.unwrap_or(MAX_RESULTS)
+            ));
+        }
+        Ok(output)
     }
 }
 
@@ -169,6 +308,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -196,6 +336,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: Some("*.rs".to_string()),
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -220,6 +361,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -252,6 +394,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -280,6 +423,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -304,6 +448,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "nonexistent".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -321,6 +466,7 @@ mod test {
                 path: temp_dir.path().to_string_lossy().to_string(),
                 regex: "[invalid".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await;
 
@@ -339,6 +485,7 @@ mod test {
                 path: "relative/path".to_string(),
                 regex: "test".to_string(),
                 file_pattern: None,
+                ..Default::default()
             })
             .await;
 
@@ -348,4 +495,218 @@ mod test {
             .to_string()
             .contains("Path must be absolute"));
     }
+
+    #[tokio::test]
+    async fn test_fs_search_case_sensitive() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(
+            temp_dir.path().join("test.txt"),
+            "TEST CONTENT\ntest content",
+        )
+        .await
+        .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                case_sensitive: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(result.ends_with("test.txt:2:test content"), "{result}");
+        assert_eq!(result.lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_literal() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(
+            temp_dir.path().join("test.rs"),
+            "let a = b.len();\nlet c = blen;",
+        )
+        .await
+        .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "b.len()".to_string(),
+                literal: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(result.ends_with("test.rs:1:let a = b.len();"), "{result}");
+        assert_eq!(result.lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_context_lines() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("test.txt");
+
+        fs::write(
+            &path,
+            "line 1\nline 2\ntest line\nline 4\nline 5\nline 6\ntest again",
+        )
+        .await
+        .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                before_context: Some(1),
+                after_context: Some(1),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let path = path.display();
+        let expected = [
+            format!("{path}-2-line 2"),
+            format!("{path}:3:test line"),
+            format!("{path}-4-line 4"),
+            "--".to_string(),
+            format!("{path}-6-line 6"),
+            format!("{path}:7:test again"),
+        ]
+        .join("\n");
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_max_results() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(temp_dir.path().join("test.txt"), "test 1\ntest 2\ntest 3")
+            .await
+            .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                max_results: Some(2),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let lines: Vec<_> = result.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("test.txt:2:test 2"));
+        assert_eq!(
+            lines[2],
+            "... results truncated after 2 matches, narrow the search or raise max_results"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_max_results_in_path_order() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["c.txt", "a.txt", "b.txt"] {
+            fs::write(temp_dir.path().join(name), "test\ntest")
+                .await
+                .unwrap();
+        }
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                max_results: Some(3),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let dir = temp_dir.path().to_path_buf();
+        let dir = dir.display();
+        let expected = format!(
+            "{dir}/a.txt:1:test\n{dir}/a.txt:2:test\n{dir}/b.txt:1:test\n... results truncated after 3 matches, narrow the search or raise max_results"
+        );
+        assert_eq!(result, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_zero_max_results() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let actual = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                max_results: Some(0),
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(actual.to_string(), "max_results has to be at least 1");
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_multiline() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(
+            temp_dir.path().join("test.rs"),
+            "fn main() {\n    run();\n}\nfn other() {}",
+        )
+        .await
+        .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: r"fn main\(\) \{\n.*run".to_string(),
+                multiline: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let lines: Vec<_> = result.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("test.rs:1:fn main() {"));
+        assert!(lines[1].ends_with("test.rs:2:    run();"));
+    }
+
+    #[tokio::test]
+    async fn test_fs_search_respects_gitignore() {
+        let temp_dir = TempDir::new().unwrap();
+
+        fs::write(temp_dir.path().join(".gitignore"), "target/\n")
+            .await
+            .unwrap();
+        fs::create_dir(temp_dir.path().join("target"))
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("target/out.txt"), "test output")
+            .await
+            .unwrap();
+        fs::write(temp_dir.path().join("test.txt"), "test source")
+            .await
+            .unwrap();
+
+        let result = FSSearch
+            .call(FSSearchInput {
+                path: temp_dir.path().to_string_lossy().to_string(),
+                regex: "test".to_string(),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert!(result.ends_with("test.txt:1:test source"), "{result}");
+        assert_eq!(result.lines().count(), 1);
+    }
 }
@@ -0,0 +1,253 @@
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Context;
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
+use forge_tool_macros::ToolDescription;
+use globset::{GlobBuilder, GlobMatcher};
+use ignore::WalkBuilder;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::tools::utils::assert_absolute_path;
+
+/// Number of paths that are returned when the input doesn't set a limit
+const MAX_RESULTS: usize = 100;
+
+#[derive(Deserialize, JsonSchema)]
+pub struct FSGlobInput {
+    /// The path of the directory to search in (absolute path required).
+    pub path: String,
+    /// Glob pattern for paths relative to the directory, e.g. '**/*_test.go'.
+    /// `*` doesn't match `/`, while `**/` matches any number of directories.
+    pub pattern: String,
+    /// Maximum number of paths. Defaults to 100.
+    pub max_results: Option<usize>,
+}
+
+/// Request to find files whose path matches a glob pattern, such as
+/// `**/*_test.go` or `src/**/mod.rs`. Use this instead of shell commands like
+/// `find` to locate files by name. Hidden files and files ignored by .gitignore
+/// are skipped. Returns absolute paths, most recently modified first, and says
+/// when the results were truncated. The path must be absolute.
+#[derive(ToolDescription)]
+pub struct FSGlob;
+
+impl NamedTool for FSGlob {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_fs_glob")
+    }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
+}
+
+/// The files below `dir` whose relative path matches the glob, along with
+/// their modification time. Unreadable entries are skipped.
+fn find(dir: &Path, glob: &GlobMatcher) -> Vec<(Option<SystemTime>, PathBuf)> {
+    // note: .gitignore files apply outside of git repositories too, as in
+    // tool_forge_fs_search
+    WalkBuilder::new(dir)
+        .require_git(false)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+        })
+        .filter(|entry| {
+            entry
+                .path()
+                .strip_prefix(dir)
+                .is_ok_and(|path| glob.is_match(path))
+        })
+        .map(|entry| {
+            let modified = entry
+                .metadata()
+                .ok()
+                .and_then(|metadata| metadata.modified().ok());
+            (modified, entry.into_path())
+        })
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for FSGlob {
+    type Input = FSGlobInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let dir = Path::new(&input.path);
+        assert_absolute_path(dir)?;
+
+        if !dir.exists() {
+            return Err(anyhow::anyhow!("Directory '{}' does not exist", input.path));
+        }
+
+        let glob = GlobBuilder::new(&input.pattern)
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern '{}'", input.pattern))?
+            .compile_matcher();
+
+        let dir = dir.to_path_buf();
+        let mut paths = tokio::task::spawn_blocking(move || find(&dir, &glob)).await?;
+        paths.sort_by(|(a_time, a_path), (b_time, b_path)| {
+            Reverse(a_time)
+                .cmp(&Reverse(b_time))
+                .then_with(|| a_path.cmp(b_path))
+        });
+
+        let max_results = input.max_results.unwrap_or(MAX_RESULTS);
+        let mut output = paths
+            .iter()
+            .take(max_results)
+            .map(|(_, path)| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if paths.len() > max_results {
+            output.push_str(&format!(
+                "\n... {} more files not shown, narrow the pattern or raise max_results",
+                paths.len() - max_results
+            ));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use pretty_assertions::assert_eq;
+    use tokio::fs;
+
+    use super::*;
+    use crate::tools::utils::TempDir;
+
+    async fn touch(path: &Path, age: u64) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await.unwrap();
+        }
+        fs::write(path, "").await.unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age);
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn input(dir: &TempDir, pattern: &str) -> FSGlobInput {
+        FSGlobInput {
+            path: dir.path().to_string_lossy().to_string(),
+            pattern: pattern.to_string(),
+            max_results: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_sorted_by_modification_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        touch(&root.join("server_test.go"), 30).await;
+        touch(&root.join("api/handler_test.go"), 10).await;
+        touch(&root.join("api/handler.go"), 0).await;
+        touch(&root.join("api/v1/routes_test.go"), 20).await;
+
+        let actual = FSGlob.call(input(&temp_dir, "**/*_test.go")).await.unwrap();
+
+        let expected = [
+            "api/handler_test.go",
+            "api/v1/routes_test.go",
+            "server_test.go",
+        ]
+        .map(|path| root.join(path).display().to_string())
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_star_stays_in_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        touch(&root.join("main.rs"), 0).await;
+        touch(&root.join("src/lib.rs"), 0).await;
+
+        let actual = FSGlob.call(input(&temp_dir, "*.rs")).await.unwrap();
+
+        assert_eq!(actual, root.join("main.rs").display().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_respects_gitignore() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        // The directory isn't a git repository
+        fs::write(root.join(".gitignore"), "target/\n")
+            .await
+            .unwrap();
+        touch(&root.join("target/debug/build.rs"), 0).await;
+        touch(&root.join("build.rs"), 0).await;
+
+        let actual = FSGlob.call(input(&temp_dir, "**/build.rs")).await.unwrap();
+
+        assert_eq!(actual, root.join("build.rs").display().to_string());
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_max_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        touch(&root.join("a.txt"), 0).await;
+        touch(&root.join("b.txt"), 10).await;
+        touch(&root.join("c.txt"), 20).await;
+
+        let actual = FSGlob
+            .call(FSGlobInput { max_results: Some(1), ..input(&temp_dir, "*.txt") })
+            .await
+            .unwrap();
+
+        let expected = format!(
+            "{}\n... 2 more files not shown, narrow the pattern or raise max_results",
+            root.join("a.txt").display()
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_no_matches() {
+        let temp_dir = TempDir::new().unwrap();
+        touch(&temp_dir.path().join("main.rs"), 0).await;
+
+        let actual = FSGlob.call(input(&temp_dir, "**/*.go")).await.unwrap();
+
+        assert!(actual.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_invalid_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let actual = FSGlob.call(input(&temp_dir, "src/[a")).await.unwrap_err();
+
+        assert_eq!(actual.to_string(), "Invalid glob pattern 'src/[a'");
+    }
+
+    #[tokio::test]
+    async fn test_fs_glob_relative_path() {
+        let actual = FSGlob
+            .call(FSGlobInput {
+                path: "relative/path".to_string(),
+                pattern: "*.rs".to_string(),
+                max_results: None,
+            })
+            .await
+            .unwrap_err();
+
+        assert!(actual.to_string().contains("Path must be absolute"));
+    }
+}
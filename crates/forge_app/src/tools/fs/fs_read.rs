@@ -1,25 +1,40 @@
 use std::path::Path;
 
 use anyhow::Context;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
+use super::extract;
 use crate::tools::utils::assert_absolute_path;
 
-#[derive(Deserialize, JsonSchema)]
+/// Number of bytes that are returned when the input doesn't set a limit
+const MAX_BYTES: usize = 40_000;
+
+#[derive(Default, Deserialize, JsonSchema)]
 pub struct FSReadInput {
     /// The path of the file to read, always provide absolute paths.
     pub path: String,
+    /// The line to start reading from, starting at 1. Defaults to the first
+    /// line.
+    pub start_line: Option<usize>,
+    /// The last line to read, inclusive. Defaults to the last line of the
+    /// file.
+    pub end_line: Option<usize>,
+    /// The maximum number of bytes to return. Defaults to 40000. Output that
+    /// exceeds it ends at the last complete line that fits.
+    pub max_bytes: Option<usize>,
 }
 
 /// Request to read the contents of a file at the specified path. Use this when
 /// you need to examine the contents of an existing file you do not know the
 /// contents of, for example to analyze code, review text files, or extract
-/// information from configuration files. Automatically extracts raw text from
-/// PDF and DOCX files. May not be suitable for other types of binary files, as
-/// it returns the raw content as a string.
+/// information from configuration files. Extracts the text of PDF, DOCX and ODT
+/// files, and of spreadsheets (XLSX, ODS) as CSV. Fails for other binary
+/// files. Lines are prefixed with their number, and the <file> tag states the
+/// lines returned, the total lines and whether the output was truncated. Page
+/// through large files with start_line and end_line.
 #[derive(ToolDescription)]
 pub struct FSRead;
 
@@ -27,6 +42,10 @@ impl NamedTool for FSRead {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_read")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
 }
 
 #[async_trait::async_trait]
@@ -37,10 +56,74 @@ impl ExecutableTool for FSRead {
         let path = Path::new(&input.path);
         assert_absolute_path(path)?;
 
-        tokio::fs::read_to_string(path)
+        let bytes = tokio::fs::read(path)
             .await
-            .with_context(|| format!("Failed to read file content from {}", input.path))
+            .with_context(|| format!("Failed to read file content from {}", input.path))?;
+        // Extracting the text of large documents takes a while
+        let content = tokio::task::spawn_blocking(move || extract::text(bytes))
+            .await?
+            .with_context(|| format!("Failed to read file content from {}", input.path))?;
+
+        read_lines(&input, &content)
+    }
+}
+
+/// Formats the requested range of lines with their line numbers
+fn read_lines(input: &FSReadInput, content: &str) -> anyhow::Result<String> {
+    let lines = content.lines().collect::<Vec<_>>();
+    let total_lines = lines.len();
+
+    let start_line = input.start_line.unwrap_or(1);
+    let end_line = input.end_line.unwrap_or(total_lines).min(total_lines);
+    if start_line == 0 {
+        anyhow::bail!("start_line has to be at least 1");
+    }
+    if start_line > total_lines.max(1) {
+        anyhow::bail!(
+            "start_line {start_line} is beyond the end of the file, which has {total_lines} lines"
+        );
     }
+    if input.end_line.is_some_and(|end_line| end_line < start_line) {
+        anyhow::bail!("end_line has to be at least start_line {start_line}");
+    }
+    if input.max_bytes == Some(0) {
+        anyhow::bail!("max_bytes has to be at least 1");
+    }
+
+    let max_bytes = input.max_bytes.unwrap_or(MAX_BYTES);
+    let mut output = String::new();
+    let mut last_line = start_line - 1;
+    let mut truncated = false;
+    for (index, line) in lines.iter().enumerate().take(end_line).skip(start_line - 1) {
+        let numbered = format!("{}:{}\n", index + 1, line);
+        if output.len() + numbered.len() > max_bytes {
+            truncated = true;
+            // A single line that exceeds the limit is cut, so that the
+            // output is never empty
+            if output.is_empty() {
+                let mut end = max_bytes;
+                while !numbered.is_char_boundary(end) {
+                    end -= 1;
+                }
+                output.push_str(&numbered[..end]);
+                output.push('\n');
+                last_line = index + 1;
+            }
+            break;
+        }
+        output.push_str(&numbered);
+        last_line = index + 1;
+    }
+
+    let lines = if last_line >= start_line {
+        format!(r#" lines="{start_line}-{last_line}""#)
+    } else {
+        String::new()
+    };
+    Ok(format!(
+        "<file path=\"{}\"{lines} total_lines=\"{total_lines}\" truncated=\"{truncated}\">\n{output}</file>",
+        input.path
+    ))
 }
 
 #[cfg(test)]
@@ -61,11 +144,18 @@ mod test {
 
         let fs_read = FSRead;
         let result = fs_read
-            .call(FSReadInput { path: file_path.to_string_lossy().to_string() })
+            .call(FSReadInput {
+                path: file_path.to_string_lossy().to_string(),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(result, test_content);
+        let expected = format!(
+            "<file path=\"{}\" lines=\"1-1\" total_lines=\"1\" truncated=\"false\">\n1:{test_content}\n</file>",
+            file_path.display()
+        );
+        assert_eq!(result, expected);
     }
 
     #[tokio::test]
@@ -75,7 +165,10 @@ mod test {
 
         let fs_read = FSRead;
         let result = fs_read
-            .call(FSReadInput { path: nonexistent_file.to_string_lossy().to_string() })
+            .call(FSReadInput {
+                path: nonexistent_file.to_string_lossy().to_string(),
+                ..Default::default()
+            })
             .await;
 
         assert!(result.is_err());
@@ -89,11 +182,90 @@ mod test {
 
         let fs_read = FSRead;
         let result = fs_read
-            .call(FSReadInput { path: file_path.to_string_lossy().to_string() })
+            .call(FSReadInput {
+                path: file_path.to_string_lossy().to_string(),
+                ..Default::default()
+            })
             .await
             .unwrap();
 
-        assert_eq!(result, "");
+        let expected = format!(
+            "<file path=\"{}\" total_lines=\"0\" truncated=\"false\">\n</file>",
+            file_path.display()
+        );
+        assert_eq!(result, expected);
+    }
+
+    fn numbered_lines(count: usize) -> String {
+        (1..=count).map(|line| format!("line {line}\n")).collect()
+    }
+
+    #[test]
+    fn test_read_lines_range() {
+        let input = FSReadInput {
+            path: "/logs/app.log".to_string(),
+            start_line: Some(3),
+            end_line: Some(4),
+            ..Default::default()
+        };
+
+        let actual = read_lines(&input, &numbered_lines(10)).unwrap();
+        let expected = "<file path=\"/logs/app.log\" lines=\"3-4\" total_lines=\"10\" truncated=\"false\">\n3:line 3\n4:line 4\n</file>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_read_lines_truncated_at_byte_cap() {
+        let input = FSReadInput {
+            path: "/logs/app.log".to_string(),
+            start_line: Some(9),
+            max_bytes: Some(30),
+            ..Default::default()
+        };
+
+        let actual = read_lines(&input, &numbered_lines(20)).unwrap();
+        let expected = "<file path=\"/logs/app.log\" lines=\"9-10\" total_lines=\"20\" truncated=\"true\">\n9:line 9\n10:line 10\n</file>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_read_lines_cuts_long_line() {
+        let input = FSReadInput {
+            path: "/data.min.js".to_string(),
+            max_bytes: Some(8),
+            ..Default::default()
+        };
+
+        let actual = read_lines(&input, "abcdefghijklmnop\nq").unwrap();
+        let expected = "<file path=\"/data.min.js\" lines=\"1-1\" total_lines=\"2\" truncated=\"true\">\n1:abcdef\n</file>";
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_read_lines_zero_max_bytes() {
+        let input = FSReadInput {
+            path: "/logs/app.log".to_string(),
+            max_bytes: Some(0),
+            ..Default::default()
+        };
+
+        let actual = read_lines(&input, &numbered_lines(10)).unwrap_err();
+        assert_eq!(actual.to_string(), "max_bytes has to be at least 1");
+    }
+
+    #[test]
+    fn test_read_lines_beyond_end() {
+        let input = FSReadInput {
+            path: "/logs/app.log".to_string(),
+            start_line: Some(11),
+            ..Default::default()
+        };
+
+        let actual = read_lines(&input, &numbered_lines(10)).unwrap_err();
+        assert_eq!(
+            actual.to_string(),
+            "start_line 11 is beyond the end of the file, which has 10 lines"
+        );
     }
 
     #[test]
@@ -105,7 +277,7 @@ mod test {
     async fn test_fs_read_relative_path() {
         let fs_read = FSRead;
         let result = fs_read
-            .call(FSReadInput { path: "relative/path.txt".to_string() })
+            .call(FSReadInput { path: "relative/path.txt".to_string(), ..Default::default() })
             .await;
 
         assert!(result.is_err());
@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use anyhow::Context;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -24,6 +24,10 @@ impl NamedTool for FSRemove {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_remove")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Write
+    }
 }
 
 #[async_trait::async_trait]
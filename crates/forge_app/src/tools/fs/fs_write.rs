@@ -2,7 +2,7 @@ use std::path::Path;
 
 use anyhow::Context;
 use forge_display::DiffFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -38,6 +38,10 @@ impl NamedTool for FSWrite {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_create")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Write
+    }
 }
 
 #[async_trait::async_trait]
@@ -1,5 +1,7 @@
+mod extract;
 mod file_info;
 mod fs_find;
+mod fs_glob;
 mod fs_list;
 mod fs_read;
 mod fs_remove;
@@ -7,6 +9,7 @@ mod fs_write;
 
 pub use file_info::*;
 pub use fs_find::*;
+pub use fs_glob::*;
 pub use fs_list::*;
 pub use fs_read::*;
 pub use fs_remove::*;
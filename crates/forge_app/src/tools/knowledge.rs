@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use forge_domain::{
-    ExecutableTool, NamedTool, Suggestion, SuggestionService, ToolDescription, ToolName,
+    ExecutableTool, NamedTool, Suggestion, SuggestionService, ToolDescription, ToolKind, ToolName,
 };
 use schemars::JsonSchema;
 
@@ -46,6 +46,10 @@ impl<F> NamedTool for RecallSuggestions<F> {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_suggestion_get".to_string())
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Network
+    }
 }
 
 pub struct StoreSuggestion<F> {
@@ -90,4 +94,8 @@ impl<F> NamedTool for StoreSuggestion<F> {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_suggestion_set".to_string())
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Write
+    }
 }
@@ -1,6 +1,7 @@
 mod fetch;
 mod fs;
 mod knowledge;
+mod outline;
 mod patch;
 mod shell;
 mod syn;
@@ -13,6 +14,7 @@ use fetch::Fetch;
 use forge_domain::{SuggestionService, Tool};
 use fs::*;
 use knowledge::{RecallSuggestions, StoreSuggestion};
+use outline::Outline;
 use patch::*;
 use shell::Shell;
 use think::Think;
@@ -27,6 +29,8 @@ pub fn tools<F: Infrastructure, S: SuggestionService>(infra: Arc<F>, suggest: Ar
         FSRemove.into(),
         FSList::default().into(),
         FSSearch.into(),
+        FSGlob.into(),
+        Outline.into(),
         FSFileInfo.into(),
         // TODO: once ApplyPatchJson is stable we can delete ApplyPatch
         ApplyPatch.into(),
@@ -67,6 +71,8 @@ mod tests {
                 provider_url: Default::default(),
                 provider_key: Default::default(),
                 openai_key: Default::default(),
+                retry_config: Default::default(),
+                replay: None,
             },
         }
     }
@@ -0,0 +1,335 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
+use forge_tool_macros::ToolDescription;
+use forge_walker::Walker;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tree_sitter::{Node, Parser};
+
+use crate::tools::syn;
+use crate::tools::utils::assert_absolute_path;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of files that are outlined when the path is a directory
+const MAX_FILES: usize = 100;
+/// Number of characters after which signatures are cut
+const MAX_SIGNATURE_LENGTH: usize = 120;
+/// Separates the outlines of the files in a directory
+const SEPARATOR: &str = "\n|----\n";
+
+#[derive(Deserialize, JsonSchema)]
+pub struct OutlineInput {
+    /// The path of the file or directory to outline (absolute path required).
+    /// Directories are outlined recursively.
+    pub path: String,
+}
+
+/// Lists the definitions in source files, such as functions, types, classes and
+/// the methods inside them, with their signatures and line ranges. Use this to
+/// find where something is defined without reading whole files, then read the
+/// relevant lines with tool_forge_fs_read. Supports Rust, Python, JavaScript,
+/// TypeScript, Java, Scala, Go, Ruby, C++ and CSS. The path can be a file or a
+/// directory and must be absolute.
+#[derive(ToolDescription)]
+pub struct Outline;
+
+impl NamedTool for Outline {
+    fn tool_name() -> ToolName {
+        ToolName::new("tool_forge_code_outline")
+    }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Scope {
+    /// Definition whose body isn't outlined
+    Leaf,
+    /// Definition whose members are outlined below it
+    Container,
+}
+
+/// Node kinds that define something, for the grammar of each extension that
+/// [`syn::extension`] supports
+fn definitions(extension: &str) -> &'static [(&'static str, Scope)] {
+    use Scope::*;
+    match extension.to_lowercase().as_str() {
+        "rs" => &[
+            ("function_item", Leaf),
+            ("function_signature_item", Leaf),
+            ("struct_item", Leaf),
+            ("enum_item", Leaf),
+            ("union_item", Leaf),
+            ("type_item", Leaf),
+            ("const_item", Leaf),
+            ("static_item", Leaf),
+            ("macro_definition", Leaf),
+            ("trait_item", Container),
+            ("impl_item", Container),
+            ("mod_item", Container),
+        ],
+        "py" => &[
+            ("function_definition", Leaf),
+            ("class_definition", Container),
+        ],
+        "ts" | "js" | "tsx" => &[
+            ("function_declaration", Leaf),
+            ("generator_function_declaration", Leaf),
+            ("variable_declarator", Leaf),
+            ("method_definition", Leaf),
+            ("method_signature", Leaf),
+            ("abstract_method_signature", Leaf),
+            ("type_alias_declaration", Leaf),
+            ("enum_declaration", Leaf),
+            ("class_declaration", Container),
+            ("abstract_class_declaration", Container),
+            ("interface_declaration", Container),
+            ("internal_module", Container),
+        ],
+        "java" => &[
+            ("method_declaration", Leaf),
+            ("constructor_declaration", Leaf),
+            ("annotation_type_declaration", Leaf),
+            ("class_declaration", Container),
+            ("interface_declaration", Container),
+            ("enum_declaration", Container),
+            ("record_declaration", Container),
+        ],
+        "scala" => &[
+            ("function_definition", Leaf),
+            ("function_declaration", Leaf),
+            ("type_definition", Leaf),
+            ("class_definition", Container),
+            ("object_definition", Container),
+            ("trait_definition", Container),
+            ("enum_definition", Container),
+        ],
+        "go" => &[
+            ("function_declaration", Leaf),
+            ("method_declaration", Leaf),
+            ("type_declaration", Leaf),
+        ],
+        "rb" => &[
+            ("method", Leaf),
+            ("singleton_method", Leaf),
+            ("class", Container),
+            ("module", Container),
+        ],
+        "cpp" | "cc" | "cxx" | "c++" => &[
+            ("function_definition", Leaf),
+            ("enum_specifier", Leaf),
+            ("class_specifier", Container),
+            ("struct_specifier", Container),
+            ("namespace_definition", Container),
+        ],
+        "css" => &[
+            ("rule_set", Leaf),
+            ("keyframes_statement", Leaf),
+            ("media_statement", Container),
+            ("supports_statement", Container),
+        ],
+        _ => &[],
+    }
+}
+
+/// Some node kinds only define something in certain shapes
+fn is_definition(node: Node) -> bool {
+    match node.kind() {
+        // `const handler = () => {}`, but not `const count = 1`
+        "variable_declarator" => node.child_by_field_name("value").is_some_and(|value| {
+            matches!(
+                value.kind(),
+                "arrow_function" | "function_expression" | "function"
+            )
+        }),
+        // `struct User { .. }`, but not `struct User user;`
+        "class_specifier" | "struct_specifier" | "enum_specifier" => {
+            node.child_by_field_name("body").is_some()
+        }
+        _ => true,
+    }
+}
+
+struct Definition {
+    signature: String,
+    start_line: usize,
+    end_line: usize,
+    members: Vec<Definition>,
+}
+
+/// The definition up to its body, on a single line
+fn signature(node: Node, source: &str) -> String {
+    // Declarations like `const handler = () => {}` start with the keyword
+    let start = match node.parent() {
+        Some(parent)
+            if node.kind() == "variable_declarator"
+                && matches!(
+                    parent.kind(),
+                    "lexical_declaration" | "variable_declaration"
+                ) =>
+        {
+            parent.start_byte()
+        }
+        _ => node.start_byte(),
+    };
+    let body = node.child_by_field_name("body").or_else(|| {
+        node.child_by_field_name("value")
+            .and_then(|value| value.child_by_field_name("body"))
+    });
+    let text = match body {
+        Some(body) => &source[start..body.start_byte()],
+        None => source[start..node.end_byte()]
+            .lines()
+            .next()
+            .unwrap_or_default(),
+    };
+
+    let signature = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let signature = signature.trim_end_matches(['{', ':', ';', '=', ' ']);
+    if signature.chars().count() > MAX_SIGNATURE_LENGTH {
+        let cut = signature
+            .chars()
+            .take(MAX_SIGNATURE_LENGTH)
+            .collect::<String>();
+        format!("{cut}...")
+    } else {
+        signature.to_string()
+    }
+}
+
+/// Definitions below the node. Nodes that don't define anything, like
+/// `export` statements or decorators, are looked through.
+fn collect(node: Node, source: &str, kinds: &[(&str, Scope)]) -> Vec<Definition> {
+    let mut definitions = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match kinds.iter().find(|(kind, _)| *kind == child.kind()) {
+            Some((_, scope)) if is_definition(child) => {
+                let members = match scope {
+                    Scope::Container => collect(child, source, kinds),
+                    Scope::Leaf => Vec::new(),
+                };
+                definitions.push(Definition {
+                    signature: signature(child, source),
+                    start_line: child.start_position().row + 1,
+                    end_line: child.end_position().row + 1,
+                    members,
+                });
+            }
+            Some(_) => {}
+            None => definitions.extend(collect(child, source, kinds)),
+        }
+    }
+    definitions
+}
+
+fn format(definitions: &[Definition], depth: usize, output: &mut String) {
+    for definition in definitions {
+        let lines = if definition.start_line == definition.end_line {
+            definition.start_line.to_string()
+        } else {
+            format!("{}-{}", definition.start_line, definition.end_line)
+        };
+        output.push_str(&format!(
+            "\n{}{lines}: {}",
+            "  ".repeat(depth),
+            definition.signature
+        ));
+        format(&definition.members, depth + 1, output);
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .filter(|extension| !definitions(extension).is_empty())
+        .map(str::to_string)
+}
+
+/// The outline of a file, below its name
+fn outline_file(path: &Path, name: &str) -> anyhow::Result<Option<String>> {
+    let extension = extension(path)
+        .with_context(|| format!("Outlines aren't supported for {}", path.display()))?;
+    let language = syn::extension(&extension)
+        .with_context(|| format!("Outlines aren't supported for {}", path.display()))?;
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file content from {}", path.display()))?;
+
+    let mut parser = Parser::new();
+    parser.set_language(&language)?;
+    let tree = parser
+        .parse(&source, None)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let definitions = collect(tree.root_node(), &source, definitions(&extension));
+    if definitions.is_empty() {
+        return Ok(None);
+    }
+    let mut output = name.to_string();
+    format(&definitions, 0, &mut output);
+    Ok(Some(output))
+}
+
+/// The outlines of the source files below the directory, in path order
+fn outline_dir(dir: &Path, files: Vec<String>) -> String {
+    let total = files.len();
+    let outlines = files
+        .iter()
+        .take(MAX_FILES)
+        // Files that can't be read, like those that aren't UTF-8, are skipped
+        .filter_map(|file| outline_file(&dir.join(file), file).ok().flatten())
+        .collect::<Vec<_>>();
+    if outlines.is_empty() {
+        return "No definitions found in supported source files".to_string();
+    }
+
+    let mut output = outlines.join(SEPARATOR);
+    if total > MAX_FILES {
+        output.push_str(&format!(
+            "\n... outlined the first {MAX_FILES} of {total} source files, outline a subdirectory for the rest"
+        ));
+    }
+    output
+}
+
+#[async_trait::async_trait]
+impl ExecutableTool for Outline {
+    type Input = OutlineInput;
+
+    async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        let path = PathBuf::from(&input.path);
+        assert_absolute_path(&path)?;
+
+        if !path.exists() {
+            return Err(anyhow::anyhow!("Path '{}' does not exist", input.path));
+        }
+
+        if path.is_file() {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let outline = tokio::task::spawn_blocking(move || outline_file(&path, &name)).await??;
+            return Ok(outline.unwrap_or_else(|| format!("No definitions found in {}", input.path)));
+        }
+
+        let mut files = Walker::max_all()
+            .cwd(path.clone())
+            .get()
+            .await
+            .with_context(|| format!("Failed to walk directory '{}'", input.path))?
+            .into_iter()
+            .filter(|file| !file.is_dir() && extension(Path::new(&file.path)).is_some())
+            .map(|file| file.path)
+            .collect::<Vec<_>>();
+        files.sort();
+
+        Ok(tokio::task::spawn_blocking(move || outline_dir(&path, files)).await?)
+    }
+}
@@ -2,7 +2,7 @@ use forge_domain::ExecutableTool;
 use insta::assert_snapshot;
 use tokio::fs;
 
-use crate::outline::{Outline, OutlineInput};
+use super::super::{Outline, OutlineInput};
 use crate::tools::utils::TempDir;
 
 #[tokio::test]
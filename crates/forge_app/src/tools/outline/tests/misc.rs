@@ -2,7 +2,7 @@ use forge_domain::ExecutableTool;
 use insta::assert_snapshot;
 use tokio::fs;
 
-use crate::outline::{Outline, OutlineInput};
+use super::super::{definitions, Outline, OutlineInput};
 use crate::tools::utils::TempDir;
 
 #[tokio::test]
@@ -75,3 +75,42 @@ async fn test_outline_unsupported_files() {
 
     assert_snapshot!("outline_unsupported_files", result);
 }
+
+#[tokio::test]
+async fn test_outline_single_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("lib.rs");
+    fs::write(
+        &file_path,
+        "pub mod config;\n\npub trait Store {\n    fn get(&self, key: &str) -> Option<String>;\n}\n",
+    )
+    .await
+    .unwrap();
+
+    let outline = Outline;
+    let result = outline
+        .call(OutlineInput { path: file_path.to_string_lossy().to_string() })
+        .await
+        .unwrap();
+
+    assert_snapshot!("outline_single_file", result);
+}
+
+#[test]
+fn test_definition_kinds_exist_in_grammars() {
+    let extensions = [
+        "rs", "py", "ts", "tsx", "java", "scala", "go", "rb", "cpp", "css",
+    ];
+    let missing = extensions
+        .iter()
+        .flat_map(|extension| {
+            let language = crate::tools::syn::extension(extension).unwrap();
+            definitions(extension)
+                .iter()
+                .filter(move |(kind, _)| language.id_for_node_kind(kind, true) == 0)
+                .map(move |(kind, _)| format!("{extension}: {kind}"))
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(missing, Vec::<String>::new());
+}
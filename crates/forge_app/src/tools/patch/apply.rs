@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use anyhow::bail;
 use dissimilar::Chunk;
 use forge_display::DiffFormat;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use schemars::JsonSchema;
 use serde::Deserialize;
 use thiserror::Error;
@@ -38,6 +38,10 @@ impl NamedTool for ApplyPatch {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_fs_patch")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Write
+    }
 }
 
 impl ToolDescription for ApplyPatch {
@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use dissimilar::Chunk;
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -177,6 +177,10 @@ impl NamedTool for ApplyPatchJson {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_patch_v2")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Write
+    }
 }
 
 /// Format the modified content as XML with optional syntax warning
@@ -1,5 +1,6 @@
 use std::io::{self, Write};
 
+use forge_domain::CancellationToken;
 use tokio::io::AsyncRead;
 use tokio::process::Command;
 
@@ -7,19 +8,22 @@ use tokio::process::Command;
 #[derive(Debug)]
 pub struct CommandExecutor {
     command: Command,
+    cancel: CancellationToken,
 }
 
 pub struct Output {
     pub stdout: String,
     pub stderr: String,
     pub success: bool,
+    /// Whether the command was killed before it finished
+    pub cancelled: bool,
 }
 
 impl CommandExecutor {
     /// Create a new command executor with the specified command and working
     /// directory
     pub fn new(command: Command) -> Self {
-        Self { command }
+        Self { command, cancel: CancellationToken::new() }
     }
 
     /// Enable colored output for the command. bydefault it's disabled.
@@ -28,6 +32,13 @@ impl CommandExecutor {
         self
     }
 
+    /// Kill the command once the token is cancelled. The output captured until
+    /// then is still returned.
+    pub fn cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     fn configure_pipes(&mut self) {
         // in order to stream the output of the command to stdout and stderr,
         // we need to set it to piped. but to pass the input to the child process
@@ -47,12 +58,26 @@ impl CommandExecutor {
         let mut stdout_pipe = child.stdout.take();
         let mut stderr_pipe = child.stderr.take();
 
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+
         // stream the output of the command to stdout and stderr.
-        let (status, stdout, stderr) = tokio::try_join!(
-            child.wait(),
-            stream(&mut stdout_pipe, io::stdout()),
-            stream(&mut stderr_pipe, io::stderr())
-        )?;
+        let status = tokio::select! {
+            result = async {
+                tokio::try_join!(
+                    child.wait(),
+                    stream(&mut stdout_pipe, io::stdout(), &mut stdout),
+                    stream(&mut stderr_pipe, io::stderr(), &mut stderr)
+                )
+            } => Some(result?.0),
+            _ = self.cancel.cancelled() => None,
+        };
+
+        // Processes spawned by the command can keep the pipes open, so they
+        // aren't drained after the command is killed
+        if status.is_none() {
+            child.kill().await?;
+        }
 
         // Drop happens after `try_join` due to <https://github.com/tokio-rs/tokio/issues/4309>
         drop(stdout_pipe);
@@ -62,19 +87,20 @@ impl CommandExecutor {
         let process_output = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
 
         Ok(Output {
-            success: status.success(),
+            success: status.is_some_and(|status| status.success()),
             stdout: process_output(&stdout),
             stderr: process_output(&stderr),
+            cancelled: status.is_none(),
         })
     }
 }
 
-/// reads the output from A and writes it to W
+/// reads the output from A, writes it to W and captures it in the output
 async fn stream<A: AsyncRead + Unpin, W: Write>(
     io: &mut Option<A>,
     mut writer: W,
-) -> io::Result<Vec<u8>> {
-    let mut output = Vec::new();
+    output: &mut Vec<u8>,
+) -> io::Result<()> {
     use tokio::io::AsyncReadExt;
     if let Some(io) = io.as_mut() {
         let mut buff = [0; 1024];
@@ -89,5 +115,5 @@ async fn stream<A: AsyncRead + Unpin, W: Write>(
             output.extend_from_slice(&buff[..n]);
         }
     }
-    Ok(output)
+    Ok(())
 }
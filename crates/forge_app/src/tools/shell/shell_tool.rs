@@ -1,7 +1,10 @@
 use std::path::PathBuf;
 
 use anyhow::bail;
-use forge_domain::{Environment, ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{
+    CancellationToken, Environment, Error, ExecutableTool, NamedTool, ToolDescription, ToolKind,
+    ToolName,
+};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -47,7 +50,9 @@ fn format_output(output: Output) -> anyhow::Result<String> {
         formatted_output
     };
 
-    if output.success {
+    if output.cancelled {
+        Err(anyhow::anyhow!(result).context(Error::Cancelled))
+    } else if output.success {
         Ok(result)
     } else {
         Err(anyhow::anyhow!(result))
@@ -75,6 +80,10 @@ impl NamedTool for Shell {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_process_shell")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Execute
+    }
 }
 
 #[async_trait::async_trait]
@@ -82,6 +91,14 @@ impl ExecutableTool for Shell {
     type Input = ShellInput;
 
     async fn call(&self, input: Self::Input) -> anyhow::Result<String> {
+        self.call_cancellable(input, CancellationToken::new()).await
+    }
+
+    async fn call_cancellable(
+        &self,
+        input: Self::Input,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<String> {
         // Validate empty command
         if input.command.trim().is_empty() {
             bail!("Command string is empty or contains only whitespace".to_string());
@@ -116,7 +133,13 @@ impl ExecutableTool for Shell {
         // Kill the command when the handler is dropped
         command.kill_on_drop(true);
 
-        format_output(CommandExecutor::new(command).colored().execute().await?)
+        format_output(
+            CommandExecutor::new(command)
+                .colored()
+                .cancellation(cancel)
+                .execute()
+                .await?,
+        )
     }
 }
 
@@ -146,6 +169,8 @@ mod tests {
             qdrant_cluster: None,
             pid: std::process::id(),
             openai_key: None,
+            retry_config: Default::default(),
+            replay: None,
         }
     }
 
@@ -378,4 +403,30 @@ mod tests {
             "Full path commands should work in normal shell"
         );
     }
+
+    #[cfg(target_family = "unix")]
+    #[tokio::test]
+    async fn test_shell_cancelled_keeps_partial_output() {
+        let shell = Shell::new(test_env());
+        let cancel = CancellationToken::new();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            canceller.cancel();
+        });
+
+        let error = shell
+            .call_cancellable(
+                ShellInput {
+                    command: "echo 'started'; sleep 10".to_string(),
+                    cwd: env::current_dir().unwrap(),
+                },
+                cancel,
+            )
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.to_string(), "Cancelled by user");
+        assert_eq!(error.root_cause().to_string(), "<stdout>started\n</stdout>");
+    }
 }
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::{Context, Result};
-use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolName};
+use forge_domain::{ExecutableTool, NamedTool, ToolDescription, ToolKind, ToolName};
 use forge_tool_macros::ToolDescription;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -136,6 +136,10 @@ impl NamedTool for Think {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_process_think")
     }
+
+    fn tool_kind() -> ToolKind {
+        ToolKind::Read
+    }
 }
 
 #[async_trait::async_trait]
@@ -3,7 +3,10 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
 use crate::template::Template;
-use crate::{Environment, ModelId, ToolName, UserContext};
+use crate::{
+    Environment, FallbackModel, ModelId, OutputSchema, ProviderId, Sampling, Thinking, ToolName,
+    UserContext,
+};
 
 #[derive(Debug, Default, Setters, Clone, Serialize, Deserialize)]
 #[setters(strip_option)]
@@ -49,6 +52,33 @@ fn truth() -> bool {
 pub struct Agent {
     pub id: AgentId,
     pub model: ModelId,
+
+    /// Name of a provider declared in the workflow that serves the model.
+    /// Uses the provider configured in the environment when unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provider: Option<ProviderId>,
+
+    /// Models that are tried in order when the model or any of the fallbacks
+    /// before it is unavailable, after all retries were exhausted
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub fallback_models: Vec<FallbackModel>,
+
+    /// Enables extended thinking with the given budget for models that
+    /// support it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub thinking: Option<Thinking>,
+
+    /// JSON schema that the agent's final answer has to follow. The answer is
+    /// requested in structured mode from providers that support it, and
+    /// answers that don't match are sent back to the model to be corrected.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output_schema: Option<OutputSchema>,
+
+    /// Sampling parameters such as the temperature, which are checked against
+    /// the parameters that the model supports
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sampling: Option<Sampling>,
+
     pub description: Option<String>,
     pub system_prompt: Template<SystemContext>,
     pub user_prompt: Template<UserContext>,
@@ -82,6 +112,13 @@ pub struct Agent {
     /// Maximum number of turns the agent can take    
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub max_turns: Option<u64>,
+
+    /// Enables concurrent execution of consecutive read-only tool calls within
+    /// a turn, running at most the given number of them at once. Tools that
+    /// modify the system are always executed one at a time. When unset, all
+    /// tool calls are executed sequentially.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_parallel_tool_calls: Option<usize>,
 }
 
 /// Transformations that can be applied to the agent's context before sending it
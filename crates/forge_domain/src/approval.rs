@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::{ToolCallFull, ToolKind};
+
+/// The user's answer to an [`ApprovalRequest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approval {
+    /// Executes the tool call
+    AllowOnce,
+    /// Executes the tool call and every following call to the same tool in the
+    /// conversation without asking again
+    AllowAlways,
+    /// Rejects the tool call with a reason that is passed on to the model
+    Deny(String),
+}
+
+/// Asks the user to approve a tool call before it is executed
+#[derive(Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub tool_call: ToolCallFull,
+    pub kind: ToolKind,
+    #[serde(skip)]
+    responder: Arc<Mutex<Option<oneshot::Sender<Approval>>>>,
+}
+
+impl std::fmt::Debug for ApprovalRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalRequest")
+            .field("tool_call", &self.tool_call)
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+impl ApprovalRequest {
+    /// Creates a request along with the receiver for its answer. The receiver
+    /// fails if the request is dropped without being answered.
+    pub fn new(tool_call: ToolCallFull, kind: ToolKind) -> (Self, oneshot::Receiver<Approval>) {
+        let (tx, rx) = oneshot::channel();
+        let request = Self { tool_call, kind, responder: Arc::new(Mutex::new(Some(tx))) };
+        (request, rx)
+    }
+
+    /// Answers the request. Only the first answer is taken into account.
+    pub fn respond(&self, approval: Approval) {
+        let responder = self
+            .responder
+            .lock()
+            .ok()
+            .and_then(|mut responder| responder.take());
+
+        if let Some(responder) = responder {
+            // The orchestrator might have stopped waiting already
+            let _ = responder.send(approval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::ToolName;
+
+    #[tokio::test]
+    async fn test_only_first_response_is_used() {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_fs_create"));
+        let (request, response) = ApprovalRequest::new(call, ToolKind::Write);
+
+        request
+            .clone()
+            .respond(Approval::Deny("Not now".to_string()));
+        request.respond(Approval::AllowOnce);
+
+        assert_eq!(
+            response.await.unwrap(),
+            Approval::Deny("Not now".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_request_fails_response() {
+        let call = ToolCallFull::new(ToolName::new("tool_forge_fs_create"));
+        let (request, response) = ApprovalRequest::new(call, ToolKind::Write);
+
+        drop(request);
+
+        assert!(response.await.is_err());
+    }
+}
@@ -1,17 +1,24 @@
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
-use crate::ConversationId;
+use crate::{ConversationId, Image};
 
 #[derive(Debug, Serialize, Deserialize, Clone, Setters)]
 #[setters(into, strip_option)]
 pub struct ChatRequest {
     pub content: String,
     pub conversation_id: ConversationId,
+    /// Images that the user attached to the message
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub images: Vec<Image>,
 }
 
 impl ChatRequest {
     pub fn new(content: impl ToString, conversation_id: ConversationId) -> Self {
-        Self { content: content.to_string(), conversation_id }
+        Self {
+            content: content.to_string(),
+            conversation_id,
+            images: Vec::new(),
+        }
     }
 }
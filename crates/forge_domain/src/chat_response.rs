@@ -1,6 +1,8 @@
 use serde::Serialize;
 
-use crate::{Event, ToolCallFull, ToolResult, Usage};
+use crate::{
+    ApprovalRequest, Compaction, Cost, Event, ModelSwitch, Retry, ToolCallFull, ToolResult, Usage,
+};
 
 /// Events that are emitted by the agent for external consumption. This includes
 /// events for all internal state changes.
@@ -8,8 +10,24 @@ use crate::{Event, ToolCallFull, ToolResult, Usage};
 #[serde(rename_all = "camelCase")]
 pub enum ChatResponse {
     Text(String),
+    /// Part of the model's reasoning that precedes its answer
+    Reasoning(String),
     ToolCallStart(ToolCallFull),
     ToolCallEnd(ToolResult),
     Usage(Usage),
     Custom(Event),
+    /// Emitted before executing a tool call that needs to be approved by the
+    /// user. The tool call is executed only after the request is answered.
+    ApprovalRequest(ApprovalRequest),
+    /// Emitted when the context didn't fit into the model's context window and
+    /// had to be compacted before retrying
+    ContextCompacted(Compaction),
+    /// Emitted when a request to the provider failed and is about to be sent
+    /// again
+    Retry(Retry),
+    /// Emitted when the model is unavailable and the request is sent to the
+    /// next fallback model instead
+    ModelSwitched(ModelSwitch),
+    /// Emitted after every request to a model whose prices are known
+    Cost(Cost),
 }
@@ -0,0 +1,11 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Files that were modified during a turn of the conversation. Their content
+/// from before the turn is kept, so that they can be restored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub turn: u64,
+    pub files: Vec<PathBuf>,
+}
@@ -0,0 +1,152 @@
+use derive_more::derive::Display;
+use serde::Serialize;
+
+use crate::{Context, ContextMessage, Role};
+
+/// Tool results longer than this are shortened when compacting a context
+const MAX_TOOL_RESULT_CHARS: usize = 10_000;
+
+const DROPPED_TOOL_RESULT: &str = "[Tool output removed to fit the context window]";
+
+/// Steps taken to make a context fit into the model's context window, from
+/// the least to the most destructive one.
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStep {
+    #[display("truncated large tool results")]
+    TruncateToolResults,
+    #[display("dropped tool results of previous turns")]
+    DropStaleToolResults,
+    #[display("summarized the oldest turns")]
+    SummarizeTurns,
+}
+
+impl CompactionStep {
+    pub const ALL: [CompactionStep; 3] = [
+        CompactionStep::TruncateToolResults,
+        CompactionStep::DropStaleToolResults,
+        CompactionStep::SummarizeTurns,
+    ];
+}
+
+/// Describes what was removed from a context that exceeded the model's
+/// context window
+#[derive(Debug, Display, Clone, PartialEq, Eq, Serialize)]
+#[display("{step}, {tokens_before} -> {tokens_after} tokens")]
+pub struct Compaction {
+    pub step: CompactionStep,
+    /// Number of tool results or turns that were compacted
+    pub count: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl Context {
+    /// Keeps only the beginning and the end of large tool results. Returns the
+    /// number of truncated results.
+    pub fn truncate_tool_results(&mut self) -> usize {
+        let mut count = 0;
+        for message in self.messages.iter_mut() {
+            if let ContextMessage::ToolMessage(result) = message {
+                if let Some(content) = truncate(&result.content, MAX_TOOL_RESULT_CHARS) {
+                    result.content = content;
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Replaces the tool results that precede the last user message with a
+    /// placeholder. Returns the number of dropped results.
+    pub fn drop_stale_tool_results(&mut self) -> usize {
+        let last_user = self
+            .messages
+            .iter()
+            .rposition(|message| message.has_role(Role::User))
+            .unwrap_or_default();
+
+        let mut count = 0;
+        for message in self.messages[..last_user].iter_mut() {
+            if let ContextMessage::ToolMessage(result) = message {
+                if result.content != DROPPED_TOOL_RESULT {
+                    result.content = DROPPED_TOOL_RESULT.to_string();
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+}
+
+/// Shortens the text to roughly `max` characters by cutting out its middle
+fn truncate(text: &str, max: usize) -> Option<String> {
+    let count = text.chars().count();
+    if count <= max {
+        return None;
+    }
+
+    let half = max / 2;
+    let head = text.chars().take(half).collect::<String>();
+    let tail = text.chars().skip(count - half).collect::<String>();
+    Some(format!(
+        "{head}\n... [{} characters truncated to fit the context window] ...\n{tail}",
+        count - 2 * half
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::{ToolName, ToolResult};
+
+    fn tool_result(content: impl Into<String>) -> ContextMessage {
+        ContextMessage::tool_result(ToolResult::new(ToolName::new("tool")).success(content))
+    }
+
+    fn tool_contents(context: &Context) -> Vec<String> {
+        context
+            .messages
+            .iter()
+            .filter_map(|message| match message {
+                ContextMessage::ToolMessage(result) => Some(result.content.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_truncate_tool_results() {
+        let large = format!("{}{}", "a".repeat(MAX_TOOL_RESULT_CHARS), "b".repeat(10));
+        let mut context = Context::default()
+            .add_message(tool_result("small"))
+            .add_message(tool_result(large));
+
+        let actual = context.truncate_tool_results();
+        assert_eq!(actual, 1);
+
+        let contents = tool_contents(&context);
+        assert_eq!(contents[0], "small");
+        assert!(contents[1].starts_with(&"a".repeat(MAX_TOOL_RESULT_CHARS / 2)));
+        assert!(contents[1].contains("[10 characters truncated"));
+        assert!(contents[1].ends_with("bbbbbbbbbb"));
+    }
+
+    #[test]
+    fn test_drop_stale_tool_results() {
+        let mut context = Context::default()
+            .add_message(ContextMessage::user("first"))
+            .add_message(tool_result("old"))
+            .add_message(ContextMessage::user("second"))
+            .add_message(tool_result("new"));
+
+        assert_eq!(context.drop_stale_tool_results(), 1);
+        assert_eq!(context.drop_stale_tool_results(), 0);
+
+        let actual = tool_contents(&context);
+        let expected = vec![DROPPED_TOOL_RESULT.to_string(), "new".to_string()];
+        assert_eq!(actual, expected);
+    }
+}
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use tracing::debug;
 
 use super::{ToolCallFull, ToolResult};
-use crate::{ToolChoice, ToolDefinition};
+use crate::{Image, OutputSchema, Reasoning, Sampling, Thinking, ToolChoice, ToolDefinition};
 
 /// Represents a message being sent to the LLM provider
 /// NOTE: ToolResults message are part of the larger Request object and not part
@@ -22,6 +22,8 @@ impl ContextMessage {
             role: Role::User,
             content: content.to_string(),
             tool_calls: None,
+            reasoning: None,
+            images: None,
         }
         .into()
     }
@@ -31,6 +33,8 @@ impl ContextMessage {
             role: Role::System,
             content: content.to_string(),
             tool_calls: None,
+            reasoning: None,
+            images: None,
         }
         .into()
     }
@@ -42,10 +46,28 @@ impl ContextMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls,
+            reasoning: None,
+            images: None,
         }
         .into()
     }
 
+    /// Attaches the reasoning that preceded an assistant message
+    pub fn with_reasoning(mut self, reasoning: Vec<Reasoning>) -> Self {
+        if let ContextMessage::ContentMessage(message) = &mut self {
+            message.reasoning = (!reasoning.is_empty()).then_some(reasoning);
+        }
+        self
+    }
+
+    /// Attaches images to the message
+    pub fn with_images(mut self, images: Vec<Image>) -> Self {
+        if let ContextMessage::ContentMessage(message) = &mut self {
+            message.images = (!images.is_empty()).then_some(images);
+        }
+        self
+    }
+
     pub fn content(&self) -> String {
         match self {
             ContextMessage::ContentMessage(message) => message.content.to_string(),
@@ -72,6 +94,12 @@ pub struct ContentMessage {
     pub role: Role,
     pub content: String,
     pub tool_calls: Option<Vec<ToolCallFull>>,
+    /// Reasoning of the model that preceded an assistant message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<Vec<Reasoning>>,
+    /// Images that the user attached to the message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub images: Option<Vec<Image>>,
 }
 
 impl ContentMessage {
@@ -80,6 +108,8 @@ impl ContentMessage {
             role: Role::Assistant,
             content: content.to_string(),
             tool_calls: None,
+            reasoning: None,
+            images: None,
         }
     }
 }
@@ -100,6 +130,14 @@ pub struct Context {
     pub tools: Vec<ToolDefinition>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<Thinking>,
+    /// Requests the answer as JSON that follows the schema, from providers
+    /// that support structured output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<OutputSchema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<Sampling>,
 }
 
 impl Context {
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use derive_more::derive::Display;
@@ -6,7 +6,9 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{Agent, AgentId, Context, Error, Event, EventType, Workflow};
+use crate::{
+    Agent, AgentId, Context, Error, Event, EventType, ToolKind, ToolName, TurnSummary, Workflow,
+};
 
 #[derive(Debug, Display, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(transparent)]
@@ -35,6 +37,16 @@ pub struct Conversation {
     pub state: HashMap<AgentId, AgentState>,
     pub events: Vec<Event>,
     pub workflow: Workflow,
+    /// Tools that the user allowed to be executed without approval
+    #[serde(default)]
+    pub allowed_tools: HashSet<ToolName>,
+    /// Summaries of context turns, keyed by the hash of the turn's content
+    #[serde(default)]
+    pub summaries: HashMap<String, TurnSummary>,
+    /// Money spent on the conversation in USD, as far as the prices of the
+    /// models are known
+    #[serde(default)]
+    pub cost: f64,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -51,9 +63,30 @@ impl Conversation {
             archived: false,
             state: Default::default(),
             events: Default::default(),
+            allowed_tools: Default::default(),
+            summaries: Default::default(),
+            cost: 0.0,
         }
     }
 
+    /// Checks if calls to the tool need to be approved by the user
+    pub fn requires_approval(&self, tool: &ToolName, kind: ToolKind) -> bool {
+        self.workflow.require_approval.contains(&kind) && !self.allowed_tools.contains(tool)
+    }
+
+    /// Number of messages the user has sent in the conversation so far
+    pub fn turn(&self) -> u64 {
+        self.events
+            .iter()
+            .filter(|event| {
+                matches!(
+                    event.event_type,
+                    EventType::UserTaskInit(_) | EventType::UserTaskUpdate(_)
+                )
+            })
+            .count() as u64
+    }
+
     pub fn turn_count(&self, id: &AgentId) -> Option<u64> {
         self.state.get(id).map(|s| s.turn_count)
     }
@@ -73,6 +106,16 @@ impl Conversation {
         self.state.get(id).and_then(|s| s.context.as_ref())
     }
 
+    /// The title generated for the conversation, if any
+    pub fn title(&self) -> Option<String> {
+        self.rfind_event(Event::TITLE).map(|event| event.value())
+    }
+
+    /// Timestamp of the most recent event in the conversation
+    pub fn updated_at(&self) -> Option<&str> {
+        self.events.last().map(|event| event.timestamp.as_str())
+    }
+
     pub fn rfind_event(&self, event_name: &str) -> Option<&Event> {
         self.events
             .iter()
@@ -85,3 +128,41 @@ impl Conversation {
             .rfind(|event| &event.event_type == event_type)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_requires_approval() {
+        let workflow = Workflow {
+            require_approval: vec![ToolKind::Write],
+            ..Default::default()
+        };
+        let mut conversation = Conversation::new(ConversationId::generate(), workflow);
+        let write = ToolName::new("tool_forge_fs_create");
+        let read = ToolName::new("tool_forge_fs_read");
+
+        assert!(conversation.requires_approval(&write, ToolKind::Write));
+        assert!(!conversation.requires_approval(&read, ToolKind::Read));
+
+        conversation.allowed_tools.insert(write.clone());
+        assert!(!conversation.requires_approval(&write, ToolKind::Write));
+    }
+
+    #[test]
+    fn test_turn_counts_user_messages() {
+        let mut conversation = Conversation::new(ConversationId::generate(), Workflow::default());
+        assert_eq!(conversation.turn(), 0);
+
+        conversation.events.push(Event::task_init("Hello"));
+        conversation
+            .events
+            .push(Event::new(EventType::Title("Greeting".into())));
+        conversation.events.push(Event::task_update("Bye"));
+
+        assert_eq!(conversation.turn(), 2);
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Usage;
+
+/// Price of a model's tokens in USD per token
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pricing {
+    pub prompt: f64,
+    pub completion: f64,
+    /// Price of prompt tokens that are read from the cache. Cached tokens are
+    /// billed like any other prompt token when unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cache_read: Option<f64>,
+    /// Price of prompt tokens that are written to the cache. Cached tokens are
+    /// billed like any other prompt token when unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub cache_write: Option<f64>,
+}
+
+impl Pricing {
+    /// Cost of a request with the given usage in USD
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        let uncached_tokens = usage
+            .prompt_tokens
+            .saturating_sub(usage.cache_read_tokens)
+            .saturating_sub(usage.cache_write_tokens);
+
+        uncached_tokens as f64 * self.prompt
+            + usage.cache_read_tokens as f64 * self.cache_read.unwrap_or(self.prompt)
+            + usage.cache_write_tokens as f64 * self.cache_write.unwrap_or(self.prompt)
+            + usage.completion_tokens as f64 * self.completion
+    }
+}
+
+/// Money spent on requests to the models in USD
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cost {
+    /// Spent since the user's last message
+    pub turn: f64,
+    /// Spent over the whole conversation
+    pub total: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_with_cached_tokens() {
+        let pricing = Pricing {
+            prompt: 0.000003,
+            completion: 0.000015,
+            cache_read: Some(0.0000003),
+            cache_write: Some(0.00000375),
+        };
+        let usage = Usage {
+            prompt_tokens: 3000,
+            completion_tokens: 100,
+            total_tokens: 3100,
+            cache_read_tokens: 2000,
+            cache_write_tokens: 500,
+        };
+
+        // 500 * 3e-6 + 2000 * 3e-7 + 500 * 3.75e-6 + 100 * 1.5e-5
+        let actual = pricing.cost(&usage);
+        let expected = 0.0015 + 0.0006 + 0.001875 + 0.0015;
+        assert!((actual - expected).abs() < 1e-12, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_cost_without_cache_pricing() {
+        let pricing = Pricing { prompt: 0.000001, completion: 0.000002, ..Default::default() };
+        let usage = Usage {
+            prompt_tokens: 1000,
+            completion_tokens: 10,
+            total_tokens: 1010,
+            cache_read_tokens: 400,
+            cache_write_tokens: 0,
+        };
+
+        let actual = pricing.cost(&usage);
+        assert!((actual - 0.00102).abs() < 1e-12, "{actual}");
+    }
+}
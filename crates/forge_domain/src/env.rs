@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
+use crate::RetryConfig;
+
 #[derive(Debug, Setters, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[setters(strip_option)]
@@ -30,6 +32,29 @@ pub struct Environment {
     pub provider_url: String,
     /// The OpenAI API key required to use embedding models.
     pub openai_key: Option<String>,
+    /// How failed requests to the provider are retried
+    pub retry_config: RetryConfig,
+    /// Records the responses of the providers or replays recorded ones
+    pub replay: Option<Replay>,
+}
+
+/// Fixture files that the responses of the providers are recorded to, so that
+/// conversations can be replayed deterministically without network access
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replay {
+    pub mode: ReplayMode,
+    /// Directory of the fixture files
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplayMode {
+    /// Sends the requests to the provider and writes the responses to fixtures
+    Record,
+    /// Answers the requests from the fixtures, failing for unknown requests
+    Replay,
 }
 
 impl Environment {
@@ -41,6 +66,14 @@ impl Environment {
         self.base_path.join("logs")
     }
 
+    pub fn conversation_path(&self) -> PathBuf {
+        self.db_path().join("conversations")
+    }
+
+    pub fn checkpoint_path(&self) -> PathBuf {
+        self.db_path().join("checkpoints")
+    }
+
     pub fn history_path(&self) -> PathBuf {
         self.base_path.join(".forge_history")
     }
@@ -2,7 +2,7 @@ use std::pin::Pin;
 
 use thiserror::Error;
 
-use crate::{AgentId, ConversationId};
+use crate::{AgentId, ConversationId, ProviderId};
 
 // NOTE: Deriving From for error is a really bad idea. This is because you end
 // up converting errors incorrectly without much context. For eg: You don't want
@@ -25,6 +25,9 @@ pub enum Error {
     #[error("Agent not found in the arena: {0}")]
     AgentUndefined(AgentId),
 
+    #[error("Provider not declared in the workflow: {0}")]
+    ProviderUndefined(ProviderId),
+
     #[error("Variable not found in output: {0}")]
     UndefinedVariable(String),
 
@@ -36,6 +39,67 @@ pub enum Error {
 
     #[error("Conversation not found: {0}")]
     ConversationNotFound(ConversationId),
+
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+
+    #[error("Cancelled by user")]
+    Cancelled,
+
+    #[error("Provider unavailable: {0}")]
+    ProviderUnavailable(String),
+
+    #[error("Agent '{0}' failed to answer in the format of its output schema: {1}")]
+    OutputSchemaMismatch(AgentId, String),
+
+    #[error("Invalid sampling parameters of agent '{0}': {1}")]
+    InvalidSampling(AgentId, String),
+
+    #[error("Spending cap of ${cap:.2} reached, the conversation has cost ${spent:.4}")]
+    SpendingCapExceeded { spent: f64, cap: f64 },
+}
+
+impl Error {
+    /// Creates a [`Error::ContextLengthExceeded`] if the error message reported
+    /// by a provider says that the context doesn't fit into the model's
+    /// context window.
+    pub fn context_length_exceeded(message: &str) -> Option<Self> {
+        let lowercase = message.to_lowercase();
+        let patterns = [
+            "context length",
+            "context_length_exceeded",
+            "context window",
+            "prompt is too long",
+            "too many tokens",
+        ];
+
+        patterns
+            .iter()
+            .any(|pattern| lowercase.contains(pattern))
+            .then(|| Error::ContextLengthExceeded(message.to_string()))
+    }
+
+    /// Checks if the error or any of its causes is a
+    /// [`Error::ContextLengthExceeded`]
+    pub fn is_context_length_exceeded(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<Error>(),
+                Some(Error::ContextLengthExceeded(_))
+            )
+        })
+    }
+
+    /// Checks if the error or any of its causes is a
+    /// [`Error::ProviderUnavailable`]
+    pub fn is_provider_unavailable(error: &anyhow::Error) -> bool {
+        error.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<Error>(),
+                Some(Error::ProviderUnavailable(_))
+            )
+        })
+    }
 }
 
 pub type Result<A> = std::result::Result<A, Error>;
@@ -43,3 +107,39 @@ pub type BoxStream<A, E> =
     Pin<Box<dyn tokio_stream::Stream<Item = std::result::Result<A, E>> + Send>>;
 
 pub type ResultStream<A, E> = std::result::Result<BoxStream<A, E>, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_length_exceeded() {
+        let messages = [
+            "prompt is too long: 210000 tokens > 200000 maximum",
+            "This endpoint's maximum context length is 128000 tokens. However, you requested about 130000 tokens",
+        ];
+        for message in messages {
+            assert!(Error::context_length_exceeded(message).is_some());
+        }
+
+        assert!(Error::context_length_exceeded("Overloaded").is_none());
+    }
+
+    #[test]
+    fn test_is_context_length_exceeded() {
+        let error = anyhow::Error::from(Error::ContextLengthExceeded("too long".to_string()))
+            .context("Failed to create completion message");
+        assert!(Error::is_context_length_exceeded(&error));
+
+        let error = anyhow::anyhow!("Overloaded").context("Failed");
+        assert!(!Error::is_context_length_exceeded(&error));
+    }
+
+    #[test]
+    fn test_is_provider_unavailable() {
+        let error = anyhow::Error::from(Error::ProviderUnavailable("529 Overloaded".to_string()))
+            .context("Failed to chat");
+        assert!(Error::is_provider_unavailable(&error));
+        assert!(!Error::is_context_length_exceeded(&error));
+    }
+}
@@ -2,7 +2,7 @@ use derive_setters::Setters;
 use schemars::{schema_for, JsonSchema};
 use serde::{Deserialize, Serialize};
 
-use crate::{NamedTool, ToolCallFull, ToolDefinition, ToolName};
+use crate::{NamedTool, ToolCallFull, ToolDefinition, ToolKind, ToolName};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(tag = "type", content = "value")]
@@ -69,6 +69,11 @@ impl NamedTool for Event {
     fn tool_name() -> ToolName {
         ToolName::new("tool_forge_event_dispatch")
     }
+
+    fn tool_kind() -> ToolKind {
+        // Agents that handle the event can write files and run commands
+        ToolKind::Execute
+    }
 }
 
 impl Event {
@@ -86,6 +91,7 @@ impl Event {
             description: "Dispatches an event with the provided name and value".to_string(),
             input_schema: schema_for!(Self),
             output_schema: None,
+            kind: Self::tool_kind(),
         }
     }
 
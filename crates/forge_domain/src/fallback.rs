@@ -0,0 +1,25 @@
+use derive_more::derive::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::{ModelId, ProviderId};
+
+/// A model that serves an agent when the models before it are unavailable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackModel {
+    pub model: ModelId,
+
+    /// Name of a provider declared in the workflow that serves the model.
+    /// Uses the provider configured in the environment when unset.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub provider: Option<ProviderId>,
+}
+
+/// Describes a switch to the next model after the previous one failed on every
+/// attempt
+#[derive(Debug, Display, Clone, PartialEq, Eq, Serialize)]
+#[display("switched from {from} to {to}: {reason}")]
+pub struct ModelSwitch {
+    pub from: ModelId,
+    pub to: ModelId,
+    pub reason: String,
+}
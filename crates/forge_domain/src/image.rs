@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::{Context, ContextMessage};
+
+const OMITTED_IMAGE: &str = "[Image omitted, the model doesn't accept images]";
+
+/// An image that is sent to the model along with a message
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Image {
+    /// Base64 encoded content of the image
+    data: String,
+    mime_type: String,
+}
+
+impl Image {
+    pub fn new_bytes(bytes: impl AsRef<[u8]>, mime_type: impl ToString) -> Self {
+        Self {
+            data: base64::engine::general_purpose::STANDARD.encode(bytes),
+            mime_type: mime_type.to_string(),
+        }
+    }
+
+    /// The MIME type of the file if it's an image format that models accept
+    pub fn mime_type_of(path: &Path) -> Option<&'static str> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            "gif" => Some("image/gif"),
+            "webp" => Some("image/webp"),
+            _ => None,
+        }
+    }
+
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    pub fn mime_type(&self) -> &str {
+        &self.mime_type
+    }
+
+    /// The image as a `data:` URL
+    pub fn url(&self) -> String {
+        format!("data:{};base64,{}", self.mime_type, self.data)
+    }
+}
+
+impl Context {
+    pub fn has_images(&self) -> bool {
+        self.messages.iter().any(|message| match message {
+            ContextMessage::ContentMessage(message) => message.images.is_some(),
+            ContextMessage::ToolMessage(_) => false,
+        })
+    }
+
+    /// Replaces the images with a note, for models that don't accept them
+    pub fn without_images(mut self) -> Self {
+        for message in self.messages.iter_mut() {
+            if let ContextMessage::ContentMessage(message) = message {
+                if let Some(images) = message.images.take() {
+                    for _ in images {
+                        message.content.push_str(&format!("\n{OMITTED_IMAGE}"));
+                    }
+                }
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_image_url() {
+        let image = Image::new_bytes(b"png", "image/png");
+        assert_eq!(image.url(), "data:image/png;base64,cG5n");
+    }
+
+    #[test]
+    fn test_mime_type_of() {
+        assert_eq!(
+            Image::mime_type_of(Path::new("screenshot.PNG")),
+            Some("image/png")
+        );
+        assert_eq!(
+            Image::mime_type_of(Path::new("photo.jpeg")),
+            Some("image/jpeg")
+        );
+        assert_eq!(Image::mime_type_of(Path::new("main.rs")), None);
+        assert_eq!(Image::mime_type_of(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_without_images() {
+        let image = Image::new_bytes(b"png", "image/png");
+        let context = Context::default()
+            .add_message(ContextMessage::user("What's this?").with_images(vec![image]));
+        assert!(context.has_images());
+
+        let actual = context.without_images();
+        assert!(!actual.has_images());
+        assert_eq!(
+            actual.messages[0].content(),
+            format!("What's this?\n{OMITTED_IMAGE}")
+        );
+    }
+}
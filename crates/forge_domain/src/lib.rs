@@ -1,53 +1,82 @@
 mod agent;
+mod approval;
 mod chat_request;
 mod chat_response;
+mod checkpoint;
+mod compaction;
 mod context;
 mod conversation;
+mod cost;
 mod env;
 mod error;
 mod event;
+mod fallback;
 mod file;
+mod image;
 mod message;
 mod model;
 mod orch;
+mod output_schema;
 mod point;
 mod provider;
+mod reasoning;
+mod retry;
+mod sampling;
 mod suggestion;
 mod summarize;
 mod template;
+mod tokenizer;
 mod tool;
 mod tool_call;
 mod tool_call_parser;
 mod tool_choice;
 mod tool_definition;
+mod tool_kind;
 mod tool_name;
 mod tool_result;
 mod tool_usage;
 mod workflow;
 
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 pub use agent::*;
+pub use approval::*;
 pub use chat_request::*;
 pub use chat_response::*;
+pub use checkpoint::*;
+pub use compaction::*;
 pub use context::*;
 pub use conversation::*;
+pub use cost::*;
 pub use env::*;
 pub use error::*;
 pub use event::*;
+pub use fallback::*;
 pub use file::*;
+pub use image::*;
 pub use message::*;
 pub use model::*;
 pub use orch::*;
+pub use output_schema::*;
 pub use point::*;
 pub use provider::*;
+pub use reasoning::*;
+pub use retry::*;
+pub use sampling::*;
 use serde::Serialize;
 pub use suggestion::*;
 pub use summarize::*;
 pub use template::*;
+pub use tokenizer::*;
+/// Used to stop a running chat cooperatively, eg: when the user interrupts it
+pub use tokio_util::sync::CancellationToken;
 pub use tool::*;
 pub use tool_call::*;
 pub use tool_call_parser::*;
 pub use tool_choice::*;
 pub use tool_definition::*;
+pub use tool_kind::*;
 pub use tool_name::*;
 pub use tool_result::*;
 pub use tool_usage::*;
@@ -64,10 +93,18 @@ pub trait ProviderService: Send + Sync + 'static {
     async fn parameters(&self, model: &ModelId) -> anyhow::Result<Parameters>;
 }
 
+/// Creates the providers that are declared in workflows
+#[async_trait::async_trait]
+pub trait ProviderRegistry: Send + Sync {
+    async fn get(&self, config: &ProviderConfig) -> anyhow::Result<Arc<dyn ProviderService>>;
+}
+
 #[async_trait::async_trait]
 pub trait ToolService: Send + Sync {
     // TODO: should take `call` by reference
-    async fn call(&self, call: ToolCallFull) -> ToolResult;
+    /// Executes the tool call. Calls that are still running when the token is
+    /// cancelled are stopped and reported as cancelled.
+    async fn call(&self, call: ToolCallFull, cancel: CancellationToken) -> ToolResult;
     fn list(&self) -> Vec<ToolDefinition>;
     fn usage_prompt(&self) -> String;
 }
@@ -76,7 +113,23 @@ pub trait ToolService: Send + Sync {
 pub trait ConversationService: Send + Sync {
     async fn get(&self, id: &ConversationId) -> anyhow::Result<Option<Conversation>>;
     async fn create(&self, workflow: Workflow) -> anyhow::Result<ConversationId>;
+    /// Lists all the known conversations, most recently active first
+    async fn list(&self) -> anyhow::Result<Vec<Conversation>>;
+    /// Inserts the conversation, replacing any existing one with the same id
+    async fn upsert(&self, conversation: Conversation) -> anyhow::Result<()>;
     async fn inc_turn(&self, id: &ConversationId, agent: &AgentId) -> anyhow::Result<()>;
+    /// Allows the tool to be used for the rest of the conversation without
+    /// asking the user for approval
+    async fn allow_tool(&self, id: &ConversationId, tool: &ToolName) -> anyhow::Result<()>;
+    /// Adds the cost of a request to the conversation's total
+    async fn add_cost(&self, id: &ConversationId, cost: f64) -> anyhow::Result<()>;
+    /// Stores the summary of a context turn, so that it can be reused
+    async fn insert_summary(
+        &self,
+        id: &ConversationId,
+        key: String,
+        summary: TurnSummary,
+    ) -> anyhow::Result<()>;
     async fn set_context(
         &self,
         id: &ConversationId,
@@ -90,6 +143,18 @@ pub trait ConversationService: Send + Sync {
     ) -> anyhow::Result<()>;
 }
 
+#[async_trait::async_trait]
+pub trait CheckpointService: Send + Sync {
+    /// Saves the current content of the file before it gets modified in the
+    /// given turn. Only the first snapshot of a file within a turn is kept.
+    async fn snapshot(&self, id: &ConversationId, turn: u64, path: &Path) -> anyhow::Result<()>;
+    /// Lists the checkpoints of the conversation, oldest turn first
+    async fn list(&self, id: &ConversationId) -> anyhow::Result<Vec<Checkpoint>>;
+    /// Restores every file modified in or after the turn to its content from
+    /// before the turn and drops the checkpoints. Returns the restored files.
+    async fn restore(&self, id: &ConversationId, turn: u64) -> anyhow::Result<Vec<PathBuf>>;
+}
+
 #[async_trait::async_trait]
 pub trait TemplateService: Send + Sync {
     async fn render<T: Serialize + Send + Sync>(
@@ -111,13 +176,17 @@ pub trait SuggestionService: Send + Sync + 'static {
 pub trait App: Send + Sync + 'static {
     type ToolService: ToolService;
     type ProviderService: ProviderService;
+    type ProviderRegistry: ProviderRegistry;
     type ConversationService: ConversationService;
     type PromptService: TemplateService;
     type SuggestionService: SuggestionService;
+    type CheckpointService: CheckpointService;
 
     fn tool_service(&self) -> &Self::ToolService;
     fn provider_service(&self) -> &Self::ProviderService;
+    fn provider_registry(&self) -> &Self::ProviderRegistry;
     fn conversation_service(&self) -> &Self::ConversationService;
     fn prompt_service(&self) -> &Self::PromptService;
     fn suggestion_service(&self) -> &Self::SuggestionService;
+    fn checkpoint_service(&self) -> &Self::CheckpointService;
 }
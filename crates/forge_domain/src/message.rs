@@ -5,13 +5,17 @@ use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
-use super::ToolCall;
+use super::{ReasoningPart, Retry, ToolCall};
 
-#[derive(Default, Clone, Debug, Serialize, PartialEq, Eq)]
+#[derive(Default, Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Usage {
     pub prompt_tokens: u64,
     pub completion_tokens: u64,
     pub total_tokens: u64,
+    /// Prompt tokens that were read from the provider's prompt cache
+    pub cache_read_tokens: u64,
+    /// Prompt tokens that were written to the provider's prompt cache
+    pub cache_write_tokens: u64,
 }
 
 impl Display for Usage {
@@ -21,7 +25,15 @@ impl Display for Usage {
                 f,
                 "[tokens {}/{}/{}]",
                 self.prompt_tokens, self.completion_tokens, self.total_tokens
-            )
+            )?;
+            if self.cache_read_tokens > 0 || self.cache_write_tokens > 0 {
+                write!(
+                    f,
+                    " [cache {}/{}]",
+                    self.cache_read_tokens, self.cache_write_tokens
+                )?;
+            }
+            Ok(())
         } else {
             Ok(())
         }
@@ -31,18 +43,24 @@ impl Display for Usage {
 /// Represents a message that was received from the LLM provider
 /// NOTE: Tool call messages are part of the larger Response object and not part
 /// of the message.
-#[derive(Default, Clone, Debug, Setters, PartialEq, Eq)]
+#[derive(Default, Clone, Debug, Setters, PartialEq, Eq, Serialize, Deserialize)]
 #[setters(into, strip_option)]
 pub struct ChatCompletionMessage {
     pub content: Option<Content>,
+    /// Part of the model's reasoning, streamed separately from the answer
+    pub reasoning: Option<ReasoningPart>,
     // TODO: rename to tool_calls (plural)
     pub tool_call: Vec<ToolCall>,
     pub finish_reason: Option<FinishReason>,
     pub usage: Option<Usage>,
+    /// Set instead of any content when the request failed and is sent again
+    #[serde(skip)]
+    pub retry: Option<Retry>,
 }
 
 /// Represents partial or full content of a message
-#[derive(Clone, Debug, PartialEq, Eq, From)]
+#[derive(Clone, Debug, PartialEq, Eq, From, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Content {
     Part(ContentPart),
     Full(ContentFull),
@@ -145,9 +163,24 @@ mod tests {
     #[test]
     fn test_usage_display() {
         // Test with non-zero tokens
-        let usage = Usage { prompt_tokens: 10, completion_tokens: 20, total_tokens: 30 };
+        let usage = Usage {
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            ..Default::default()
+        };
         assert_eq!(usage.to_string(), "[tokens 10/20/30]");
 
+        // Test with cached tokens
+        let usage = Usage {
+            prompt_tokens: 1500,
+            completion_tokens: 20,
+            total_tokens: 1520,
+            cache_read_tokens: 1200,
+            cache_write_tokens: 300,
+        };
+        assert_eq!(usage.to_string(), "[tokens 1500/20/1520] [cache 1200/300]");
+
         // Test with zero tokens
         let usage = Usage::default();
         assert_eq!(usage.to_string(), "");
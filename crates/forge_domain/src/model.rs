@@ -2,23 +2,49 @@ use derive_more::derive::Display;
 use derive_setters::Setters;
 use serde::{Deserialize, Serialize};
 
+use crate::Pricing;
+
 #[derive(Clone, Debug, Deserialize, Serialize, Setters)]
 pub struct Model {
     pub id: ModelId,
     pub name: String,
     pub description: Option<String>,
     pub context_length: Option<u64>,
+    /// Unknown for providers that don't report the prices of their models
+    pub pricing: Option<Pricing>,
+    /// Whether the model accepts images, unknown for providers that don't
+    /// report it
+    pub image_input: Option<bool>,
     // TODO: add provider information to the model
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Parameters {
     pub tool_supported: bool,
+    /// Names of the request parameters that the model supports, unknown for
+    /// providers that don't report them
+    pub supported_parameters: Option<Vec<String>>,
+    /// Highest temperature that the provider accepts, 2 when unknown
+    #[serde(default)]
+    pub max_temperature: Option<f32>,
 }
 
 impl Parameters {
     pub fn new(tool_supported: bool) -> Self {
-        Self { tool_supported }
+        Self {
+            tool_supported,
+            supported_parameters: None,
+            max_temperature: None,
+        }
+    }
+
+    /// Whether the model accepts an output schema as a request parameter.
+    /// Unknown support counts as none.
+    pub fn structured_output(&self) -> bool {
+        self.supported_parameters
+            .iter()
+            .flatten()
+            .any(|parameter| matches!(parameter.as_str(), "response_format" | "structured_outputs"))
     }
 }
 
@@ -37,3 +63,22 @@ impl ModelId {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structured_output_support() {
+        let parameters = |supported: Option<&[&str]>| Parameters {
+            supported_parameters: supported
+                .map(|supported| supported.iter().map(|name| name.to_string()).collect()),
+            ..Parameters::new(true)
+        };
+
+        assert!(parameters(Some(&["tools", "response_format"])).structured_output());
+        assert!(parameters(Some(&["structured_outputs"])).structured_output());
+        assert!(!parameters(Some(&["tools"])).structured_output());
+        assert!(!parameters(None).structured_output());
+    }
+}
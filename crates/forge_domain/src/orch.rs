@@ -1,6 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::Arc;
 
+use anyhow::Context as _;
 use async_recursion::async_recursion;
 use futures::future::join_all;
 use futures::{Stream, StreamExt};
@@ -10,6 +12,10 @@ use crate::*;
 
 type ArcSender = Arc<tokio::sync::mpsc::Sender<anyhow::Result<AgentMessage<ChatResponse>>>>;
 
+/// Number of times an agent is asked to correct an answer that doesn't match
+/// its output schema
+const MAX_OUTPUT_RETRIES: usize = 2;
+
 #[derive(Debug, Clone)]
 pub struct AgentMessage<T> {
     pub agent: AgentId,
@@ -21,11 +27,16 @@ pub struct Orchestrator<App> {
     system_context: SystemContext,
     sender: Option<Arc<ArcSender>>,
     chat_request: ChatRequest,
+    cancel: CancellationToken,
+    /// Money spent on the models since the user's message
+    turn_cost: tokio::sync::Mutex<f64>,
 }
 
 struct ChatCompletionResult {
     pub content: String,
+    pub reasoning: Vec<Reasoning>,
     pub tool_calls: Vec<ToolCallFull>,
+    pub usage: Option<Usage>,
 }
 
 impl<A: App> Orchestrator<A> {
@@ -34,12 +45,15 @@ impl<A: App> Orchestrator<A> {
         chat_request: ChatRequest,
         system_context: SystemContext,
         sender: Option<ArcSender>,
+        cancel: CancellationToken,
     ) -> Self {
         Self {
             app: svc,
             system_context,
             sender: sender.map(Arc::new),
             chat_request,
+            cancel,
+            turn_cost: Default::default(),
         }
     }
 
@@ -74,7 +88,46 @@ impl<A: App> Orchestrator<A> {
             .collect::<Vec<_>>()
     }
 
+    /// The provider that the workflow declares under the given name, if the
+    /// default one isn't used
+    async fn provider(
+        &self,
+        id: Option<&ProviderId>,
+    ) -> anyhow::Result<Option<Arc<dyn ProviderService>>> {
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        let conversation = self.get_conversation().await?;
+        let config = conversation.workflow.get_provider(id)?;
+        self.app
+            .provider_registry()
+            .get(config)
+            .await
+            .with_context(|| format!("Failed to create provider: {}", id))
+            .map(Some)
+    }
+
     async fn init_agent_context(&self, agent: &Agent) -> anyhow::Result<Context> {
+        self.model_context(
+            agent,
+            &agent.model,
+            agent.provider.as_ref(),
+            Context::default(),
+        )
+        .await
+    }
+
+    /// Sets the parts of the context that depend on the model: the system
+    /// prompt and the tools, which both depend on the model's tool support.
+    /// Fails if the agent's sampling parameters don't suit the model.
+    async fn model_context(
+        &self,
+        agent: &Agent,
+        model: &ModelId,
+        provider: Option<&ProviderId>,
+        context: Context,
+    ) -> anyhow::Result<Context> {
         let tool_defs = self.init_tool_definitions(agent);
 
         let tool_usage_prompt = tool_defs.iter().fold("".to_string(), |acc, tool| {
@@ -83,12 +136,14 @@ impl<A: App> Orchestrator<A> {
 
         let mut system_context = self.system_context.clone();
 
-        let tool_supported = self
-            .app
-            .provider_service()
-            .parameters(&agent.model)
-            .await?
-            .tool_supported;
+        let parameters = self.parameters(model, provider).await?;
+        if let Some(sampling) = &agent.sampling {
+            sampling.validate(&parameters).map_err(|error| {
+                Error::InvalidSampling(agent.id.clone(), format!("{error} for model {model}"))
+            })?;
+        }
+
+        let tool_supported = parameters.tool_supported;
         system_context.tool_supported = Some(tool_supported);
 
         let system_message = self
@@ -100,15 +155,26 @@ impl<A: App> Orchestrator<A> {
             )
             .await?;
 
-        Ok(Context::default()
+        Ok(context
             .set_first_system_message(system_message)
-            .extend_tools(if tool_supported {
+            .tools(if tool_supported {
                 tool_defs
             } else {
                 Vec::new()
             }))
     }
 
+    async fn parameters(
+        &self,
+        model: &ModelId,
+        provider: Option<&ProviderId>,
+    ) -> anyhow::Result<Parameters> {
+        match self.provider(provider).await? {
+            Some(provider) => provider.parameters(model).await,
+            None => self.app.provider_service().parameters(model).await,
+        }
+    }
+
     async fn collect_messages(
         &self,
         agent: &AgentId,
@@ -116,9 +182,34 @@ impl<A: App> Orchestrator<A> {
             + std::marker::Unpin,
     ) -> anyhow::Result<ChatCompletionResult> {
         let mut messages = Vec::new();
+        let mut reasoning = Vec::new();
+        let mut cancelled = false;
 
-        while let Some(message) = response.next().await {
+        loop {
+            let message = tokio::select! {
+                message = response.next() => message,
+                _ = self.cancel.cancelled() => {
+                    cancelled = true;
+                    None
+                }
+            };
+            let Some(message) = message else {
+                break;
+            };
             let message = message?;
+            if let Some(retry) = message.retry {
+                self.send(agent, ChatResponse::Retry(retry)).await?;
+                continue;
+            }
+
+            if let Some(part) = message.reasoning.clone() {
+                if let ReasoningPart::Text(text) = &part {
+                    self.send(agent, ChatResponse::Reasoning(text.clone()))
+                        .await?;
+                }
+                Reasoning::append(&mut reasoning, part);
+            }
+
             messages.push(message.clone());
             if let Some(content) = message.content {
                 self.send(agent, ChatResponse::Text(content.as_str().to_string()))
@@ -130,6 +221,8 @@ impl<A: App> Orchestrator<A> {
             }
         }
 
+        let usage = messages.iter().rev().find_map(|m| m.usage.clone());
+
         let content = messages
             .iter()
             .flat_map(|m| m.content.iter())
@@ -137,6 +230,12 @@ impl<A: App> Orchestrator<A> {
             .collect::<Vec<_>>()
             .join("");
 
+        // The content received so far is kept, but tool calls of an interrupted
+        // response can be incomplete
+        if cancelled {
+            return Ok(ChatCompletionResult { content, reasoning, tool_calls: Vec::new(), usage });
+        }
+
         // From Complete (incase streaming is disabled)
         let mut tool_calls: Vec<ToolCallFull> = messages
             .iter()
@@ -157,7 +256,7 @@ impl<A: App> Orchestrator<A> {
         // From XML
         tool_calls.extend(ToolCallFull::try_from_xml(&content)?);
 
-        Ok(ChatCompletionResult { content, tool_calls })
+        Ok(ChatCompletionResult { content, reasoning, tool_calls, usage })
     }
 
     async fn dispatch(&self, event: &Event) -> anyhow::Result<()> {
@@ -200,17 +299,426 @@ impl<A: App> Orchestrator<A> {
             self.dispatch(&event).await?;
             Ok(None)
         } else {
-            Ok(Some(self.app.tool_service().call(tool_call.clone()).await))
+            Ok(Some(
+                self.app
+                    .tool_service()
+                    .call(tool_call.clone(), self.cancel.clone())
+                    .await,
+            ))
+        }
+    }
+
+    /// Saves the current content of the file that the tool call is about to
+    /// modify, so that it can be restored with an undo.
+    async fn checkpoint(
+        &self,
+        conversation: &Conversation,
+        tool_call: &ToolCallFull,
+    ) -> anyhow::Result<()> {
+        let Some(path) = tool_call
+            .arguments
+            .get("path")
+            .and_then(|path| path.as_str())
+        else {
+            return Ok(());
+        };
+
+        self.app
+            .checkpoint_service()
+            .snapshot(&conversation.id, conversation.turn(), Path::new(path))
+            .await
+            .with_context(|| format!("Failed to create a checkpoint for {}", path))
+    }
+
+    /// Asks the user to approve the tool call if the workflow requires it.
+    /// Returns the result to report back to the model when the call is denied.
+    async fn request_approval(
+        &self,
+        agent_id: &AgentId,
+        conversation: &mut Conversation,
+        tool_call: &ToolCallFull,
+        kind: ToolKind,
+    ) -> anyhow::Result<Option<ToolResult>> {
+        if Event::parse(tool_call).is_some()
+            || !conversation.requires_approval(&tool_call.name, kind)
+        {
+            return Ok(None);
+        }
+
+        let (request, response) = ApprovalRequest::new(tool_call.clone(), kind);
+        self.send(agent_id, ChatResponse::ApprovalRequest(request))
+            .await?;
+
+        // Nobody is around to approve the call if the request gets dropped
+        let approval = response
+            .await
+            .unwrap_or(Approval::Deny("No response from the user".to_string()));
+
+        match approval {
+            Approval::AllowOnce => Ok(None),
+            Approval::AllowAlways => {
+                self.app
+                    .conversation_service()
+                    .allow_tool(&self.chat_request.conversation_id, &tool_call.name)
+                    .await?;
+                conversation.allowed_tools.insert(tool_call.name.clone());
+                Ok(None)
+            }
+            Approval::Deny(reason) => Ok(Some(ToolResult::from(tool_call.clone()).failure(
+                anyhow::anyhow!(
+                    "The user denied the call to '{}': {}",
+                    tool_call.name.as_str(),
+                    reason
+                ),
+            ))),
+        }
+    }
+
+    /// Executes the tool calls in batches, where every batch is either a run of
+    /// consecutive read-only calls that are executed concurrently, or a single
+    /// call that can modify the system. Results are returned in the same order
+    /// as the calls.
+    async fn execute_tools(
+        &self,
+        agent: &Agent,
+        tool_calls: &[ToolCallFull],
+    ) -> anyhow::Result<Vec<ToolResult>> {
+        let concurrency = agent.max_parallel_tool_calls.unwrap_or(1).max(1);
+        let kinds = self
+            .init_default_tool_definitions()
+            .into_iter()
+            .map(|tool| (tool.name, tool.kind))
+            .collect::<HashMap<_, _>>();
+        let read_only = kinds
+            .iter()
+            .filter(|(_, kind)| kind.is_read_only())
+            .map(|(name, _)| name.clone())
+            .collect::<HashSet<_>>();
+        let mut conversation = self.get_conversation().await?;
+
+        let mut tool_results = Vec::new();
+        for batch in tool_call_batches(tool_calls, &read_only, concurrency) {
+            // Approvals are requested one call at a time, before any call in the
+            // batch is executed
+            let mut skipped = Vec::new();
+            for tool_call in batch {
+                self.send(&agent.id, ChatResponse::ToolCallStart(tool_call.clone()))
+                    .await?;
+                let kind = kinds.get(&tool_call.name).copied().unwrap_or_default();
+
+                // Every call still gets a result, so that the context remains
+                // valid after the user interrupts the turn
+                let mut result = if self.cancel.is_cancelled() {
+                    Some(ToolResult::from(tool_call.clone()).failure(Error::Cancelled.into()))
+                } else {
+                    self.request_approval(&agent.id, &mut conversation, tool_call, kind)
+                        .await?
+                };
+                if result.is_none() && kind == ToolKind::Write {
+                    if let Err(error) = self.checkpoint(&conversation, tool_call).await {
+                        result = Some(ToolResult::from(tool_call.clone()).failure(error));
+                    }
+                }
+                skipped.push(result);
+            }
+
+            let executions = batch
+                .iter()
+                .zip(skipped)
+                .map(|(tool_call, skipped)| async move {
+                    match skipped {
+                        Some(tool_result) => Ok(Some(tool_result)),
+                        None => self.execute_tool(&agent.id, tool_call).await,
+                    }
+                })
+                .collect::<Vec<_>>();
+            let mut results = futures::stream::iter(executions).buffered(concurrency);
+
+            while let Some(tool_result) = results.next().await {
+                if let Some(tool_result) = tool_result? {
+                    tool_results.push(tool_result.clone());
+                    self.send(&agent.id, ChatResponse::ToolCallEnd(tool_result))
+                        .await?;
+                }
+            }
+        }
+
+        Ok(tool_results)
+    }
+
+    /// Summarizes the oldest turns of the context with the given agent until
+    /// the context fits into the token limit. Returns the number of summarized
+    /// turns.
+    async fn summarize(
+        &self,
+        model: &ModelId,
+        agent_id: &AgentId,
+        token_limit: usize,
+        input_key: &str,
+        output_key: &str,
+        context: &mut Context,
+    ) -> anyhow::Result<usize> {
+        let summaries = self.get_conversation().await?.summaries;
+        let tokenizer = model.tokenizer();
+        let mut summarize = Summarize::new(context, token_limit, &tokenizer);
+        let mut count = 0;
+        while let Some(mut summary) = summarize.summarize() {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
+            let key = summary.key();
+            if let Some(cached) = summaries.get(&key) {
+                debug!(key = %key, "Reusing cached summary");
+                summary.set(&cached.summary);
+                count += 1;
+                continue;
+            }
+
+            let input = Event::new_name_value(input_key, summary.get());
+            self.init_agent(agent_id, &input).await?;
+
+            if let Some(value) = self.get_last_event(output_key).await? {
+                let messages = summary.messages();
+                let content = serde_json::to_string(&value)?;
+                summary.set(&content);
+                count += 1;
+                self.app
+                    .conversation_service()
+                    .insert_summary(
+                        &self.chat_request.conversation_id,
+                        key,
+                        TurnSummary { messages, summary: content },
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Sends the context to the model. If the context doesn't fit into the
+    /// model's context window, it is compacted step by step and sent again.
+    /// If the model is unavailable, the agent's fallback models are tried in
+    /// order, each with the system prompt and tools set up for it. No request
+    /// is sent once the conversation reached the workflow's spending cap.
+    async fn chat(
+        &self,
+        agent: &Agent,
+        context: &mut Context,
+    ) -> anyhow::Result<ChatCompletionResult> {
+        let mut steps = CompactionStep::ALL.into_iter();
+        let mut fallbacks = agent.fallback_models.iter();
+        let mut model = &agent.model;
+        let mut provider = agent.provider.as_ref();
+        loop {
+            self.check_spending_cap().await?;
+
+            // note: The images stay in the context, in case a fallback model accepts them
+            let mut request = context.clone();
+            // The stored context stays set up for the agent's own model
+            if model != &agent.model || provider != agent.provider.as_ref() {
+                request = self.model_context(agent, model, provider, request).await?;
+            }
+            if request.output_schema.is_some()
+                && !self.parameters(model, provider).await?.structured_output()
+            {
+                debug!(model = %model, "Describing the output schema in the system prompt");
+                request = request.without_output_schema();
+            }
+            if request.has_images() {
+                let model_info = self.model_info(model, provider).await?;
+                if model_info.and_then(|model| model.image_input) == Some(false) {
+                    debug!(model = %model, "Omitting images the model doesn't accept");
+                    request = request.without_images();
+                }
+            }
+
+            let response = match self.provider(provider).await? {
+                Some(provider) => provider.chat(model, request).await,
+                None => self.app.provider_service().chat(model, request).await,
+            };
+            let result = match response {
+                Ok(response) => self.collect_messages(&agent.id, response).await,
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Err(error) if Error::is_context_length_exceeded(&error) => {
+                    let Some(step) = steps.next() else {
+                        return Err(error);
+                    };
+
+                    debug!(agent = %agent.id, step = %step, "Compacting context");
+                    let compaction = self.compact(agent, context, step).await?;
+                    self.send(&agent.id, ChatResponse::ContextCompacted(compaction))
+                        .await?;
+                }
+                Err(error) if Error::is_provider_unavailable(&error) => {
+                    let Some(fallback) = fallbacks.next() else {
+                        return Err(error);
+                    };
+
+                    let switch = ModelSwitch {
+                        from: model.clone(),
+                        to: fallback.model.clone(),
+                        reason: error.to_string(),
+                    };
+                    debug!(agent = %agent.id, switch = %switch, "Switching to fallback model");
+                    self.send(&agent.id, ChatResponse::ModelSwitched(switch))
+                        .await?;
+
+                    model = &fallback.model;
+                    provider = fallback.provider.as_ref();
+                }
+                Ok(mut result) => {
+                    // Not every provider reports the usage, so it is estimated instead
+                    let usage = match result.usage.clone() {
+                        Some(usage) => usage,
+                        None => {
+                            let usage = estimate_usage(model, context, &result.content);
+                            self.send(&agent.id, ChatResponse::Usage(usage.clone()))
+                                .await?;
+                            result.usage = Some(usage.clone());
+                            usage
+                        }
+                    };
+
+                    self.add_cost(&agent.id, model, provider, &usage).await?;
+                    return Ok(result);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Fails once the conversation spent the amount that the workflow allows
+    async fn check_spending_cap(&self) -> anyhow::Result<()> {
+        let conversation = self.get_conversation().await?;
+        match conversation.workflow.max_cost {
+            Some(cap) if conversation.cost >= cap => {
+                Err(Error::SpendingCapExceeded { spent: conversation.cost, cap }.into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds the cost of a request to the conversation, if the prices of the
+    /// model are known
+    async fn add_cost(
+        &self,
+        agent_id: &AgentId,
+        model: &ModelId,
+        provider: Option<&ProviderId>,
+        usage: &Usage,
+    ) -> anyhow::Result<()> {
+        let pricing = self
+            .model_info(model, provider)
+            .await?
+            .and_then(|model| model.pricing);
+        let Some(pricing) = pricing else {
+            return Ok(());
+        };
+
+        let cost = pricing.cost(usage);
+        self.app
+            .conversation_service()
+            .add_cost(&self.chat_request.conversation_id, cost)
+            .await?;
+
+        let turn = {
+            let mut turn_cost = self.turn_cost.lock().await;
+            *turn_cost += cost;
+            *turn_cost
+        };
+        let total = self.get_conversation().await?.cost;
+        self.send(agent_id, ChatResponse::Cost(Cost { turn, total }))
+            .await
+    }
+
+    /// What the provider reports about the model, if it lists the model
+    async fn model_info(
+        &self,
+        model: &ModelId,
+        provider: Option<&ProviderId>,
+    ) -> anyhow::Result<Option<Model>> {
+        let models = match self.provider(provider).await? {
+            Some(provider) => provider.models().await,
+            None => self.app.provider_service().models().await,
+        };
+        match models {
+            Ok(models) => Ok(models.into_iter().find(|candidate| candidate.id == *model)),
+            Err(error) => {
+                debug!(model = %model, error = %error, "Failed to get the models of the provider");
+                Ok(None)
+            }
         }
     }
 
+    #[async_recursion]
+    async fn compact(
+        &self,
+        agent: &Agent,
+        context: &mut Context,
+        step: CompactionStep,
+    ) -> anyhow::Result<Compaction> {
+        let tokenizer = agent.model.tokenizer();
+        let tokens_before = tokenizer.count(&context.to_text());
+
+        let count = match step {
+            CompactionStep::TruncateToolResults => context.truncate_tool_results(),
+            CompactionStep::DropStaleToolResults => context.drop_stale_tool_results(),
+            CompactionStep::SummarizeTurns => {
+                let token_limit = tokens_before / 2;
+                let summarizer = agent
+                    .transforms
+                    .iter()
+                    .find_map(|transform| match transform {
+                        Transform::Assistant { agent_id, input, output, .. } => {
+                            Some((agent_id, input, output))
+                        }
+                        _ => None,
+                    });
+
+                match summarizer {
+                    Some((agent_id, input, output)) => {
+                        self.summarize(&agent.model, agent_id, token_limit, input, output, context)
+                            .await?
+                    }
+                    // Without a summarizer the oldest turns are dropped instead
+                    None => {
+                        let mut summarize = Summarize::new(context, token_limit, &tokenizer);
+                        let mut count = 0;
+                        while let Some(mut summary) = summarize.summarize() {
+                            summary.set("[Turn removed to fit the context window]");
+                            count += 1;
+                        }
+                        count
+                    }
+                }
+            }
+        };
+
+        Ok(Compaction {
+            step,
+            count,
+            tokens_before,
+            tokens_after: tokenizer.count(&context.to_text()),
+        })
+    }
+
     #[async_recursion]
     async fn execute_transform(
         &self,
+        model: &ModelId,
         transforms: &[Transform],
         mut context: Context,
     ) -> anyhow::Result<Context> {
         for transform in transforms.iter() {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+
             match transform {
                 Transform::Assistant {
                     agent_id,
@@ -218,15 +726,15 @@ impl<A: App> Orchestrator<A> {
                     input: input_key,
                     output: output_key,
                 } => {
-                    let mut summarize = Summarize::new(&mut context, *token_limit);
-                    while let Some(mut summary) = summarize.summarize() {
-                        let input = Event::new_name_value(input_key, summary.get());
-                        self.init_agent(agent_id, &input).await?;
-
-                        if let Some(value) = self.get_last_event(output_key).await? {
-                            summary.set(serde_json::to_string(&value)?);
-                        }
-                    }
+                    self.summarize(
+                        model,
+                        agent_id,
+                        *token_limit,
+                        input_key,
+                        output_key,
+                        &mut context,
+                    )
+                    .await?;
                 }
                 Transform::User { agent_id, output: output_key } => {
                     if let Some(ContextMessage::ContentMessage(ContentMessage {
@@ -323,6 +831,11 @@ impl<A: App> Orchestrator<A> {
             }
         };
 
+        // Stored contexts follow changes to the agent's configuration
+        context.thinking = agent.thinking.clone();
+        context.output_schema = agent.output_schema.clone();
+        context.sampling = agent.sampling.clone();
+
         let mut user_context = UserContext::new(event.clone());
 
         if agent.suggestions {
@@ -337,37 +850,53 @@ impl<A: App> Orchestrator<A> {
             .render(&agent.user_prompt, &user_context)
             .await?;
 
-        context = context.add_message(ContextMessage::user(content));
+        context =
+            context.add_message(ContextMessage::user(content).with_images(self.attachments(event)));
 
+        let mut output_retries = 0;
         loop {
-            context = self.execute_transform(&agent.transforms, context).await?;
-            let response = self
-                .app
-                .provider_service()
-                .chat(&agent.model, context.clone())
+            context = self
+                .execute_transform(&agent.model, &agent.transforms, context)
                 .await?;
-            let ChatCompletionResult { tool_calls, content } =
-                self.collect_messages(&agent.id, response).await?;
+            let ChatCompletionResult { tool_calls, content, reasoning, .. } =
+                self.chat(agent, &mut context).await?;
 
-            let mut tool_results = Vec::new();
+            let tool_results = self.execute_tools(agent, &tool_calls).await?;
+            let cancelled = self.cancel.is_cancelled();
 
-            for tool_call in tool_calls.iter() {
-                self.send(&agent.id, ChatResponse::ToolCallStart(tool_call.clone()))
-                    .await?;
-                if let Some(tool_result) = self.execute_tool(&agent.id, tool_call).await? {
-                    tool_results.push(tool_result.clone());
-                    self.send(&agent.id, ChatResponse::ToolCallEnd(tool_result))
-                        .await?;
-                }
+            // Only the final answer has to follow the schema
+            let output_error = match &agent.output_schema {
+                Some(schema) if tool_calls.is_empty() && !cancelled => schema.parse(&content).err(),
+                _ => None,
+            };
+
+            // Providers reject empty assistant messages, which is what's left
+            // when the turn is interrupted before the model responded
+            if !(cancelled && content.is_empty() && tool_calls.is_empty()) {
+                let message =
+                    ContextMessage::assistant(content, Some(tool_calls)).with_reasoning(reasoning);
+                context = context
+                    .add_message(message)
+                    .add_tool_results(tool_results.clone());
             }
 
-            context = context
-                .add_message(ContextMessage::assistant(content, Some(tool_calls)))
-                .add_tool_results(tool_results.clone());
+            if let Some(error) = output_error {
+                if output_retries == MAX_OUTPUT_RETRIES {
+                    self.set_context(&agent.id, context).await?;
+                    return Err(Error::OutputSchemaMismatch(agent.id.clone(), error).into());
+                }
+                output_retries += 1;
+
+                debug!(agent = %agent.id, error = %error, "Answer doesn't match the output schema");
+                context = context.add_message(ContextMessage::user(format!(
+                    "Your answer doesn't match the output schema:\n{error}\nRespond again with only the JSON that matches the schema."
+                )));
+                continue;
+            }
 
             self.set_context(&agent.id, context.clone()).await?;
 
-            if tool_results.is_empty() {
+            if tool_results.is_empty() || cancelled {
                 break;
             }
         }
@@ -377,6 +906,19 @@ impl<A: App> Orchestrator<A> {
         Ok(())
     }
 
+    /// Images that the user attached to their message, for the events that
+    /// carry the message
+    fn attachments(&self, event: &Event) -> Vec<Image> {
+        match &event.event_type {
+            EventType::UserTaskInit(value) | EventType::UserTaskUpdate(value)
+                if *value == self.chat_request.content =>
+            {
+                self.chat_request.images.clone()
+            }
+            _ => Vec::new(),
+        }
+    }
+
     async fn init_suggestions(&self) -> anyhow::Result<Vec<String>> {
         Ok(self
             .app
@@ -408,3 +950,104 @@ impl<A: App> Orchestrator<A> {
         self.dispatch(&event).await
     }
 }
+
+/// Estimates the usage of a completion by counting the tokens of the context
+/// and of the response with the model's tokenizer.
+fn estimate_usage(model: &ModelId, context: &Context, content: &str) -> Usage {
+    let tokenizer = model.tokenizer();
+    let prompt_tokens = tokenizer.count(&context.to_text()) as u64;
+    let completion_tokens = tokenizer.count(content) as u64;
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        ..Default::default()
+    }
+}
+
+/// Groups consecutive read-only tool calls so that they can be executed
+/// together. All other calls end up in a batch of their own.
+fn tool_call_batches<'a>(
+    tool_calls: &'a [ToolCallFull],
+    read_only: &HashSet<ToolName>,
+    concurrency: usize,
+) -> Vec<&'a [ToolCallFull]> {
+    tool_calls
+        .chunk_by(|a, b| {
+            concurrency > 1 && read_only.contains(&a.name) && read_only.contains(&b.name)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn call(name: &str) -> ToolCallFull {
+        ToolCallFull::new(ToolName::new(name))
+    }
+
+    fn names(batches: Vec<&[ToolCallFull]>) -> Vec<Vec<&str>> {
+        batches
+            .into_iter()
+            .map(|batch| batch.iter().map(|call| call.name.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_tool_call_batches_groups_read_only_calls() {
+        let read_only = HashSet::from([ToolName::new("read"), ToolName::new("search")]);
+        let calls = vec![
+            call("read"),
+            call("search"),
+            call("write"),
+            call("read"),
+            call("shell"),
+            call("shell"),
+            call("read"),
+            call("read"),
+        ];
+
+        let actual = names(tool_call_batches(&calls, &read_only, 4));
+        let expected = vec![
+            vec!["read", "search"],
+            vec!["write"],
+            vec!["read"],
+            vec!["shell"],
+            vec!["shell"],
+            vec!["read", "read"],
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_tool_call_batches_without_concurrency() {
+        let read_only = HashSet::from([ToolName::new("read")]);
+        let calls = vec![call("read"), call("read")];
+
+        let actual = names(tool_call_batches(&calls, &read_only, 1));
+        let expected = vec![vec!["read"], vec!["read"]];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_estimate_usage() {
+        let model = ModelId::new("openai/gpt-4o");
+        let context = Context::default().add_message(ContextMessage::user("Hello"));
+
+        let actual = estimate_usage(&model, &context, "Hi there!");
+
+        let tokenizer = model.tokenizer();
+        assert_eq!(
+            actual.prompt_tokens,
+            tokenizer.count(&context.to_text()) as u64
+        );
+        assert_eq!(actual.completion_tokens, 3);
+        assert_eq!(
+            actual.total_tokens,
+            actual.prompt_tokens + actual.completion_tokens
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{Context, ContextMessage, Role};
+
+/// Keywords that answers are checked against, along with annotations that
+/// don't constrain the answer
+const KEYWORDS: &[&str] = &[
+    "type",
+    "enum",
+    "const",
+    "properties",
+    "required",
+    "additionalProperties",
+    "items",
+    "anyOf",
+    "minLength",
+    "maxLength",
+    "minimum",
+    "maximum",
+    "minItems",
+    "maxItems",
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+/// JSON schema that the final answer of an agent has to follow. Answers are
+/// checked against the common keywords of the schema: `type`, `enum`,
+/// `const`, `properties`, `required`, `additionalProperties`, `items`,
+/// `anyOf` and the length and range limits. Schemas with other keywords, like
+/// `$ref` or `pattern`, fail to parse rather than accepting any answer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub struct OutputSchema(Value);
+
+impl TryFrom<Value> for OutputSchema {
+    type Error = String;
+
+    fn try_from(schema: Value) -> Result<Self, Self::Error> {
+        check(&schema, "#")?;
+        Ok(Self(schema))
+    }
+}
+
+impl From<OutputSchema> for Value {
+    fn from(schema: OutputSchema) -> Self {
+        schema.0
+    }
+}
+
+impl OutputSchema {
+    pub fn new(schema: Value) -> Self {
+        Self(schema)
+    }
+
+    pub fn schema(&self) -> &Value {
+        &self.0
+    }
+
+    /// Asks for an answer that matches the schema, for models that can't be
+    /// given the schema as a request parameter
+    pub fn instruction(&self) -> String {
+        format!(
+            "Respond with only JSON that matches this schema:\n{:#}",
+            self.0
+        )
+    }
+
+    /// Parses the answer of the model and checks it against the schema. The
+    /// error describes every violation, so that it can be fed back to the
+    /// model.
+    pub fn parse(&self, answer: &str) -> Result<Value, String> {
+        let answer = answer.trim();
+        // Models tend to wrap JSON in a markdown code block
+        let answer = answer
+            .strip_prefix("```json")
+            .or_else(|| answer.strip_prefix("```"))
+            .and_then(|answer| answer.strip_suffix("```"))
+            .unwrap_or(answer);
+
+        let value = serde_json::from_str::<Value>(answer)
+            .map_err(|error| format!("The answer is not valid JSON: {error}"))?;
+
+        let mut errors = Vec::new();
+        validate(&self.0, &value, "$", &mut errors);
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+/// Fails for the first keyword of the schema that answers can't be checked
+/// against
+fn check(schema: &Value, path: &str) -> Result<(), String> {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(_) => return Ok(()),
+        _ => return Err(format!("{path}: expected a schema")),
+    };
+
+    if let Some(keyword) = schema
+        .keys()
+        .find(|keyword| !KEYWORDS.contains(&keyword.as_str()))
+    {
+        return Err(format!(
+            "{path}: the output schema keyword '{keyword}' is not supported"
+        ));
+    }
+
+    if let Some(Value::Object(properties)) = schema.get("properties") {
+        for (name, property) in properties {
+            check(property, &format!("{path}/properties/{name}"))?;
+        }
+    }
+    for keyword in ["additionalProperties", "items"] {
+        if let Some(subschema) = schema.get(keyword) {
+            check(subschema, &format!("{path}/{keyword}"))?;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("anyOf") {
+        for (index, option) in options.iter().enumerate() {
+            check(option, &format!("{path}/anyOf/{index}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(value: &Value, expected: &str) -> bool {
+    let actual = type_name(value);
+    actual == expected || (expected == "number" && actual == "integer")
+}
+
+fn validate(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Value::Object(schema) = schema else {
+        // `true` accepts any value and `false` none
+        if *schema == Value::Bool(false) {
+            errors.push(format!("{path}: no value is allowed"));
+        }
+        return;
+    };
+
+    let expected_types = match schema.get("type") {
+        Some(Value::String(expected)) => vec![expected.as_str()],
+        Some(Value::Array(expected)) => expected.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !expected_types.is_empty() && !expected_types.iter().any(|t| has_type(value, t)) {
+        errors.push(format!(
+            "{path}: expected {}, found {}",
+            expected_types.join(" or "),
+            type_name(value)
+        ));
+        return;
+    }
+
+    if let Some(Value::Array(allowed)) = schema.get("enum") {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{path}: expected one of {}",
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    if let Some(allowed) = schema.get("const") {
+        if allowed != value {
+            errors.push(format!("{path}: expected {allowed}"));
+        }
+    }
+
+    if let Some(Value::Array(options)) = schema.get("anyOf") {
+        let matches = options.iter().any(|option| {
+            let mut option_errors = Vec::new();
+            validate(option, value, path, &mut option_errors);
+            option_errors.is_empty()
+        });
+        if !matches {
+            errors.push(format!("{path}: doesn't match any of the allowed schemas"));
+        }
+    }
+
+    let limit = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+    match value {
+        Value::String(text) => {
+            let length = text.chars().count() as f64;
+            if limit("minLength").is_some_and(|min| length < min) {
+                errors.push(format!(
+                    "{path}: shorter than {} characters",
+                    schema["minLength"]
+                ));
+            }
+            if limit("maxLength").is_some_and(|max| length > max) {
+                errors.push(format!(
+                    "{path}: longer than {} characters",
+                    schema["maxLength"]
+                ));
+            }
+        }
+        Value::Number(number) => {
+            let number = number.as_f64().unwrap_or_default();
+            if limit("minimum").is_some_and(|min| number < min) {
+                errors.push(format!("{path}: less than {}", schema["minimum"]));
+            }
+            if limit("maximum").is_some_and(|max| number > max) {
+                errors.push(format!("{path}: greater than {}", schema["maximum"]));
+            }
+        }
+        Value::Array(items) => {
+            let length = items.len() as f64;
+            if limit("minItems").is_some_and(|min| length < min) {
+                errors.push(format!("{path}: fewer than {} items", schema["minItems"]));
+            }
+            if limit("maxItems").is_some_and(|max| length > max) {
+                errors.push(format!("{path}: more than {} items", schema["maxItems"]));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    validate(item_schema, item, &format!("{path}[{index}]"), errors);
+                }
+            }
+        }
+        Value::Object(object) => {
+            if let Some(Value::Array(required)) = schema.get("required") {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !object.contains_key(name) {
+                        errors.push(format!("{path}: missing required property '{name}'"));
+                    }
+                }
+            }
+
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, property) in object {
+                let property_path = format!("{path}.{name}");
+                match properties.and_then(|properties| properties.get(name)) {
+                    Some(property_schema) => {
+                        validate(property_schema, property, &property_path, errors)
+                    }
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            errors.push(format!("{property_path}: unexpected property"))
+                        }
+                        Some(additional) => validate(additional, property, &property_path, errors),
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+impl Context {
+    /// Moves the output schema into the system prompt, for models that don't
+    /// support structured outputs
+    pub fn without_output_schema(mut self) -> Self {
+        let Some(output_schema) = self.output_schema.take() else {
+            return self;
+        };
+        let instruction = output_schema.instruction();
+        match self.messages.first_mut() {
+            Some(ContextMessage::ContentMessage(message)) if message.role == Role::System => {
+                message.content = format!("{}\n\n{instruction}", message.content);
+            }
+            _ => self.messages.insert(0, ContextMessage::system(instruction)),
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn fixture() -> OutputSchema {
+        OutputSchema::new(json!({
+            "type": "object",
+            "properties": {
+                "title": {"type": "string", "maxLength": 20},
+                "tags": {"type": "array", "items": {"enum": ["bug", "feature"]}},
+                "priority": {"type": "integer", "minimum": 1}
+            },
+            "required": ["title"],
+            "additionalProperties": false
+        }))
+    }
+
+    #[test]
+    fn test_parse_valid_answer() {
+        let actual = fixture()
+            .parse("```json\n{\"title\": \"fix-login\", \"tags\": [\"bug\"]}\n```")
+            .unwrap();
+        let expected = json!({"title": "fix-login", "tags": ["bug"]});
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_parse_invalid_json() {
+        let actual = fixture().parse("The title is fix-login").unwrap_err();
+        assert!(
+            actual.starts_with("The answer is not valid JSON"),
+            "{actual}"
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_every_violation() {
+        let actual = fixture()
+            .parse(r#"{"tags": ["bug", "chore"], "priority": 0.5, "owner": "me"}"#)
+            .unwrap_err();
+        let expected = [
+            "$: missing required property 'title'",
+            "$.owner: unexpected property",
+            "$.priority: expected integer, found number",
+            r#"$.tags[1]: expected one of ["bug","feature"]"#,
+        ]
+        .join("\n");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_deserialize_supported_schema() {
+        let actual = serde_json::from_value::<OutputSchema>(fixture().schema().clone()).unwrap();
+        assert_eq!(actual, fixture());
+    }
+
+    #[test]
+    fn test_deserialize_unsupported_keyword() {
+        // Like the schemas that schemars generates for nested types
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "owner": {"$ref": "#/definitions/User"}
+            },
+            "definitions": {
+                "User": {"type": "string"}
+            }
+        });
+
+        let actual = serde_json::from_value::<OutputSchema>(schema)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            actual,
+            "#: the output schema keyword 'definitions' is not supported"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_unsupported_nested_keyword() {
+        let schema = json!({
+            "type": "array",
+            "items": {"type": "string", "pattern": "^[a-z]+$"}
+        });
+
+        let actual = serde_json::from_value::<OutputSchema>(schema)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            actual,
+            "#/items: the output schema keyword 'pattern' is not supported"
+        );
+    }
+
+    #[test]
+    fn test_without_output_schema() {
+        let schema = OutputSchema::new(json!({"type": "string"}));
+        let actual = Context::default()
+            .add_message(ContextMessage::system("You are a helper."))
+            .add_message(ContextMessage::user("Name a color."))
+            .output_schema(schema)
+            .without_output_schema();
+
+        let expected = Context::default()
+            .add_message(ContextMessage::system(
+                "You are a helper.\n\nRespond with only JSON that matches this schema:\n{\n  \"type\": \"string\"\n}",
+            ))
+            .add_message(ContextMessage::user("Name a color."));
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+use derive_more::derive::Display;
 use serde::{Deserialize, Serialize};
 
 const OPEN_ROUTER_URL: &str = "https://api.openrouter.io/v1/";
@@ -12,6 +13,9 @@ pub enum Provider {
     OpenRouter,
     OpenAI,
     Anthropic,
+    /// A server that implements the OpenAI API, eg: Ollama, vLLM or the
+    /// llama.cpp server, running at the given base URL
+    OpenAICompatible(String),
 }
 
 impl Display for Provider {
@@ -20,6 +24,7 @@ impl Display for Provider {
             Provider::OpenRouter => write!(f, "OpenRouter"),
             Provider::OpenAI => write!(f, "OpenAI"),
             Provider::Anthropic => write!(f, "Anthropic"),
+            Provider::OpenAICompatible(url) => write!(f, "OpenAI compatible ({})", url),
         }
     }
 }
@@ -41,7 +46,11 @@ impl Provider {
             (_, Ok(_), _, _) => Some(Self::OpenRouter),
             (_, _, Ok(_), _) => Some(Self::OpenAI),
             (_, _, _, Ok(_)) => Some(Self::Anthropic),
-            (Err(_), Err(_), Err(_), Err(_)) => None,
+            // note: self-hosted servers usually don't require an API key.
+            (Err(_), Err(_), Err(_), Err(_)) => {
+                let provider_url = std::env::var("FORGE_PROVIDER_URL").ok()?;
+                Self::from_url(&provider_url)
+            }
         }
     }
 
@@ -51,16 +60,119 @@ impl Provider {
             Provider::OpenRouter => OPEN_ROUTER_URL,
             Provider::OpenAI => OPENAI_URL,
             Provider::Anthropic => ANTHROPIC_URL,
+            Provider::OpenAICompatible(url) => url,
         }
     }
 
-    /// detects the active provider from base URL
+    /// detects the active provider from base URL, any other HTTP URL is
+    /// expected to serve an OpenAI compatible API
     pub fn from_url(url: &str) -> Option<Self> {
         match url {
             OPENAI_URL => Some(Self::OpenAI),
             OPEN_ROUTER_URL => Some(Self::OpenRouter),
             ANTHROPIC_URL => Some(Self::Anthropic),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                // note: without the trailing slash, joining paths would replace the last
+                // segment of the URL, eg: `/v1`.
+                Some(Self::OpenAICompatible(format!(
+                    "{}/",
+                    url.trim_end_matches('/')
+                )))
+            }
             _ => None,
         }
     }
 }
+
+/// Name under which a provider is declared in the workflow
+#[derive(Debug, Display, Eq, PartialEq, Hash, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProviderId(String);
+
+impl ProviderId {
+    pub fn new(id: impl ToString) -> Self {
+        Self(id.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// Kinds of APIs that a provider declared in the workflow can implement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProviderKind {
+    #[serde(rename = "open_router")]
+    OpenRouter,
+    #[serde(rename = "openai")]
+    OpenAI,
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
+}
+
+/// A provider that agents of the workflow can select instead of the one
+/// configured in the environment
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    /// Base URL of the API, required for OpenAI compatible providers
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub url: Option<String>,
+    /// Name of the environment variable that holds the API key
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub key_env: Option<String>,
+}
+
+impl ProviderConfig {
+    /// The provider that serves the API
+    pub fn provider(&self) -> anyhow::Result<Provider> {
+        Ok(match self.kind {
+            ProviderKind::OpenRouter => Provider::OpenRouter,
+            ProviderKind::OpenAI => Provider::OpenAI,
+            ProviderKind::Anthropic => Provider::Anthropic,
+            ProviderKind::OpenAICompatible => {
+                let url = self
+                    .url
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("OpenAI compatible providers require a url"))?;
+                match Provider::from_url(url) {
+                    Some(provider @ Provider::OpenAICompatible(_)) => provider,
+                    _ => anyhow::bail!("Invalid OpenAI compatible provider url: {}", url),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_provider_config() {
+        let config: ProviderConfig = serde_json::from_value(serde_json::json!({
+            "kind": "openai_compatible",
+            "url": "http://localhost:11434/v1",
+        }))
+        .unwrap();
+
+        let actual = config.provider().unwrap();
+        let expected = Provider::OpenAICompatible("http://localhost:11434/v1/".to_string());
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_provider_config_without_url() {
+        let config = ProviderConfig {
+            kind: ProviderKind::OpenAICompatible,
+            url: None,
+            key_env: None,
+        };
+
+        assert!(config.provider().is_err());
+    }
+}
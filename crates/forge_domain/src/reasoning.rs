@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+
+/// Lets the model reason before it answers, for models that support extended
+/// thinking
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Thinking {
+    /// Number of output tokens the model can spend on reasoning
+    pub budget_tokens: u64,
+}
+
+/// Part of the model's reasoning as it is streamed by the provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningPart {
+    Text(String),
+    /// Marks the end of a reasoning block and lets the provider verify it
+    /// when the block is sent back
+    Signature(String),
+    /// Reasoning that was encrypted by the provider
+    Redacted(String),
+}
+
+/// A block of the model's reasoning that preceded its answer. Providers expect
+/// the blocks to be sent back unchanged along with the results of the tool
+/// calls that followed them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reasoning {
+    Thinking {
+        text: String,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        signature: Option<String>,
+    },
+    Redacted {
+        data: String,
+    },
+}
+
+impl Reasoning {
+    /// Adds a streamed part to the blocks received so far. Text that follows a
+    /// signed or redacted block starts a new block.
+    pub fn append(blocks: &mut Vec<Reasoning>, part: ReasoningPart) {
+        match (blocks.last_mut(), part) {
+            (Some(Reasoning::Thinking { text, signature: None }), ReasoningPart::Text(part)) => {
+                text.push_str(&part)
+            }
+            (_, ReasoningPart::Text(text)) => {
+                blocks.push(Reasoning::Thinking { text, signature: None })
+            }
+            (Some(Reasoning::Thinking { signature, .. }), ReasoningPart::Signature(part)) => {
+                signature.get_or_insert_with(String::new).push_str(&part)
+            }
+            (_, ReasoningPart::Signature(signature)) => {
+                blocks.push(Reasoning::Thinking { text: String::new(), signature: Some(signature) })
+            }
+            (_, ReasoningPart::Redacted(data)) => blocks.push(Reasoning::Redacted { data }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_append_reasoning_parts() {
+        let parts = vec![
+            ReasoningPart::Text("Let me ".to_string()),
+            ReasoningPart::Text("think".to_string()),
+            ReasoningPart::Signature("abc".to_string()),
+            ReasoningPart::Redacted("secret".to_string()),
+            ReasoningPart::Text("Again".to_string()),
+        ];
+
+        let mut actual = Vec::new();
+        for part in parts {
+            Reasoning::append(&mut actual, part);
+        }
+
+        let expected = vec![
+            Reasoning::Thinking {
+                text: "Let me think".to_string(),
+                signature: Some("abc".to_string()),
+            },
+            Reasoning::Redacted { data: "secret".to_string() },
+            Reasoning::Thinking { text: "Again".to_string(), signature: None },
+        ];
+        assert_eq!(actual, expected);
+    }
+}
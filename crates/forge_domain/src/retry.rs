@@ -0,0 +1,74 @@
+use std::fmt::{self, Display};
+use std::time::Duration;
+
+use derive_setters::Setters;
+use serde::{Deserialize, Serialize};
+
+/// Controls how requests to a provider are retried when they fail before any
+/// part of the response was received.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Setters)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryConfig {
+    /// Number of times a request is sent before giving up
+    pub max_attempts: usize,
+    /// Delay before the first retry, doubled on every subsequent one
+    pub initial_backoff_ms: u64,
+    /// Upper bound of the delay between two attempts
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+/// Describes a failed request to the provider that is about to be sent again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Retry {
+    /// The attempt that failed, starting at 1
+    pub attempt: usize,
+    pub max_attempts: usize,
+    pub delay: Duration,
+    pub reason: String,
+}
+
+impl Display for Retry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "attempt {}/{} failed, retrying in {:.1}s: {}",
+            self.attempt,
+            self.max_attempts,
+            self.delay.as_secs_f64(),
+            self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_retry_display() {
+        let retry = Retry {
+            attempt: 1,
+            max_attempts: 5,
+            delay: Duration::from_millis(1500),
+            reason: "429 Too Many Requests".to_string(),
+        };
+
+        assert_eq!(
+            retry.to_string(),
+            "attempt 1/5 failed, retrying in 1.5s: 429 Too Many Requests"
+        );
+    }
+}
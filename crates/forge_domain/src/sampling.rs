@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Parameters;
+
+/// Sampling parameters that are sent with every request of an agent. Unset
+/// parameters are left to the provider's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sampling {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub top_k: Option<u32>,
+    /// Maximum number of tokens in the answer
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_tokens: Option<u32>,
+    /// Sequences that end the answer when the model generates them
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub stop: Vec<String>,
+}
+
+impl Sampling {
+    /// Names of the parameters that are set, as providers report them
+    fn names(&self) -> Vec<&'static str> {
+        [
+            ("temperature", self.temperature.is_some()),
+            ("top_p", self.top_p.is_some()),
+            ("top_k", self.top_k.is_some()),
+            ("max_tokens", self.max_tokens.is_some()),
+            ("stop", !self.stop.is_empty()),
+        ]
+        .into_iter()
+        .filter_map(|(name, set)| set.then_some(name))
+        .collect()
+    }
+
+    /// Checks that the values are in the provider's range and, if the provider
+    /// reports them, that the model supports the parameters
+    pub fn validate(&self, parameters: &Parameters) -> Result<(), String> {
+        let max_temperature = parameters.max_temperature.unwrap_or(2.0);
+        if self
+            .temperature
+            .is_some_and(|value| !(0.0..=max_temperature).contains(&value))
+        {
+            return Err(format!(
+                "temperature has to be between 0 and {max_temperature}"
+            ));
+        }
+        if self
+            .top_p
+            .is_some_and(|value| !(0.0..=1.0).contains(&value))
+        {
+            return Err("top_p has to be between 0 and 1".to_string());
+        }
+        if self.max_tokens == Some(0) {
+            return Err("max_tokens has to be at least 1".to_string());
+        }
+
+        if let Some(supported) = &parameters.supported_parameters {
+            let unsupported = self
+                .names()
+                .into_iter()
+                .filter(|name| !supported.iter().any(|supported| supported == name))
+                .collect::<Vec<_>>();
+            if !unsupported.is_empty() {
+                return Err(format!(
+                    "the model doesn't support {}",
+                    unsupported.join(", ")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_validate_out_of_range() {
+        let sampling = Sampling { temperature: Some(2.5), ..Default::default() };
+        let actual = sampling.validate(&Parameters::default());
+        assert_eq!(
+            actual,
+            Err("temperature has to be between 0 and 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_provider_temperature_range() {
+        let sampling = Sampling { temperature: Some(1.5), ..Default::default() };
+        let parameters = Parameters { max_temperature: Some(1.0), ..Parameters::new(true) };
+
+        let actual = sampling.validate(&parameters);
+        assert_eq!(
+            actual,
+            Err("temperature has to be between 0 and 1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_unsupported_parameters() {
+        let sampling = Sampling {
+            temperature: Some(0.2),
+            top_k: Some(40),
+            stop: vec!["</answer>".to_string()],
+            ..Default::default()
+        };
+        let parameters = Parameters {
+            tool_supported: true,
+            supported_parameters: Some(vec!["temperature".to_string(), "top_p".to_string()]),
+            max_temperature: None,
+        };
+
+        let actual = sampling.validate(&parameters);
+        assert_eq!(
+            actual,
+            Err("the model doesn't support top_k, stop".to_string())
+        );
+    }
+
+    #[test]
+    fn test_validate_unknown_support() {
+        let sampling = Sampling { top_k: Some(40), ..Default::default() };
+        assert_eq!(sampling.validate(&Parameters::default()), Ok(()));
+    }
+}
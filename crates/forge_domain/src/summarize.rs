@@ -14,19 +14,34 @@
 use std::collections::VecDeque;
 use std::ops::Range;
 
-use crate::{Context, ContextMessage, Role};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{Context, ContextMessage, Role, Tokenizer};
+
+/// A summary that replaced a turn of the context. The original messages are
+/// kept so that they can be inspected later.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TurnSummary {
+    pub messages: Vec<ContextMessage>,
+    pub summary: String,
+}
 
 pub struct Summarize<'context> {
     context: &'context mut Context,
     token_limit: usize,
+    tokenizer: &'context dyn Tokenizer,
     turns: VecDeque<Range<usize>>,
-    // TODO: use a persistent cache to avoid re-summarizing
 }
 
 impl<'context> Summarize<'context> {
-    pub fn new(context: &'context mut Context, token_limit: usize) -> Self {
+    pub fn new(
+        context: &'context mut Context,
+        token_limit: usize,
+        tokenizer: &'context dyn Tokenizer,
+    ) -> Self {
         let turns = turns(context);
-        Self { context, token_limit, turns: turns.into() }
+        Self { context, token_limit, tokenizer, turns: turns.into() }
     }
 
     fn replace(&mut self, content: impl ToString, range: Range<usize>) {
@@ -38,7 +53,7 @@ impl<'context> Summarize<'context> {
 
     /// Get a replaceable item while the total token count is above the limit
     pub fn summarize(&mut self) -> Option<Summary<'_, 'context>> {
-        let total = token_count(&self.context.to_text());
+        let total = self.tokenizer.count(&self.context.to_text());
 
         if total <= self.token_limit {
             return None;
@@ -61,15 +76,19 @@ impl Summary<'_, '_> {
     }
 
     pub fn get(&self) -> String {
-        Context::default()
-            .messages(self.summarize.context.messages[self.next_turn.clone()].to_vec())
-            .to_text()
+        Context::default().messages(self.messages()).to_text()
     }
-}
 
-// TODO: this is a quick hack to get a ballpark token count
-fn token_count(text: &str) -> usize {
-    text.split_whitespace().count() * 75 / 100
+    /// The messages of the turn that is going to be summarized
+    pub fn messages(&self) -> Vec<ContextMessage> {
+        self.summarize.context.messages[self.next_turn.clone()].to_vec()
+    }
+
+    /// Identifies the turn by its content, so that a previous summary of the
+    /// same turn can be reused.
+    pub fn key(&self) -> String {
+        format!("{:x}", Sha256::digest(self.get().as_bytes()))
+    }
 }
 
 fn turns(context: &Context) -> Vec<Range<usize>> {
@@ -91,3 +110,37 @@ fn turns(context: &Context) -> Vec<Range<usize>> {
         .map(|(start, end)| start..end)
         .collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Encoding;
+
+    fn context() -> Context {
+        Context::default()
+            .add_message(ContextMessage::user("Read the file"))
+            .add_message(ContextMessage::assistant("The file is empty", None))
+            .add_message(ContextMessage::user("Write the file"))
+            .add_message(ContextMessage::assistant("Done", None))
+    }
+
+    #[test]
+    fn test_summary_key_depends_on_content() {
+        let mut first = context();
+        let mut second = context();
+        let mut third = context().add_message(ContextMessage::user("Thanks"));
+        third.messages[0] = ContextMessage::user("Read another file");
+
+        let key = |context: &mut Context| {
+            Summarize::new(context, 0, &Encoding::Cl100kBase)
+                .summarize()
+                .map(|summary| summary.key())
+                .unwrap()
+        };
+
+        assert_eq!(key(&mut first), key(&mut second));
+        assert_ne!(key(&mut first), key(&mut third));
+    }
+}
@@ -0,0 +1,103 @@
+use tiktoken_rs::{cl100k_base_singleton, o200k_base_singleton};
+
+use crate::ModelId;
+
+/// Counts the number of tokens a model needs to represent a text
+pub trait Tokenizer: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// BPE encodings that are bundled with the binary, so that counting tokens
+/// doesn't need network access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Used by GPT-4 and GPT-3.5 models. Anthropic doesn't publish the
+    /// tokenizer of its recent models, so it is also the closest available
+    /// approximation for Claude and other model families.
+    Cl100kBase,
+    /// Used by GPT-4o and the reasoning models
+    O200kBase,
+}
+
+impl Encoding {
+    /// Selects the encoding that matches the model family. Model IDs can
+    /// contain a provider prefix, eg: `openai/gpt-4o`.
+    pub fn for_model(model: &ModelId) -> Self {
+        let name = model
+            .as_str()
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        let o200k = [
+            "gpt-4o",
+            "gpt-4.1",
+            "gpt-4.5",
+            "chatgpt-4o",
+            "o1",
+            "o3",
+            "o4",
+        ];
+        if o200k.iter().any(|prefix| name.starts_with(prefix)) {
+            Encoding::O200kBase
+        } else {
+            Encoding::Cl100kBase
+        }
+    }
+}
+
+impl Tokenizer for Encoding {
+    fn count(&self, text: &str) -> usize {
+        let bpe = match self {
+            Encoding::Cl100kBase => cl100k_base_singleton(),
+            Encoding::O200kBase => o200k_base_singleton(),
+        };
+        let count = bpe.lock().encode_ordinary(text).len();
+        count
+    }
+}
+
+impl ModelId {
+    /// Returns the tokenizer that matches the model
+    pub fn tokenizer(&self) -> impl Tokenizer {
+        Encoding::for_model(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_encoding_for_model() {
+        let actual = [
+            "openai/gpt-4o-mini",
+            "o3-mini",
+            "openai/gpt-4",
+            "anthropic/claude-3.5-sonnet",
+            "google/gemini-2.0-flash-001",
+        ]
+        .map(|model| Encoding::for_model(&ModelId::new(model)));
+
+        let expected = [
+            Encoding::O200kBase,
+            Encoding::O200kBase,
+            Encoding::Cl100kBase,
+            Encoding::Cl100kBase,
+            Encoding::Cl100kBase,
+        ];
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_count_tokens() {
+        let text = "fn main() { println!(\"Hello, world!\"); }";
+
+        assert_eq!(Encoding::Cl100kBase.count(text), 11);
+        assert_eq!(Encoding::O200kBase.count(text), 12);
+        assert_eq!(Encoding::Cl100kBase.count(""), 0);
+    }
+}
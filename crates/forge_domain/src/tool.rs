@@ -1,7 +1,7 @@
 use schemars::JsonSchema;
 use serde_json::Value;
 
-use crate::{ExecutableTool, NamedTool, ToolDefinition, ToolDescription};
+use crate::{CancellationToken, ExecutableTool, NamedTool, ToolDefinition, ToolDescription};
 
 struct JsonTool<T>(T);
 
@@ -14,7 +14,7 @@ impl<T> JsonTool<T> {
 #[async_trait::async_trait]
 impl<T: ExecutableTool + Sync> ExecutableTool for JsonTool<T>
 where
-    T::Input: serde::de::DeserializeOwned + JsonSchema,
+    T::Input: serde::de::DeserializeOwned + JsonSchema + Send,
 {
     type Input = Value;
 
@@ -22,6 +22,15 @@ where
         let input: T::Input = serde_json::from_value(input)?;
         self.0.call(input).await
     }
+
+    async fn call_cancellable(
+        &self,
+        input: Self::Input,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<String> {
+        let input: T::Input = serde_json::from_value(input)?;
+        self.0.call_cancellable(input, cancel).await
+    }
 }
 
 pub struct Tool {
@@ -32,7 +41,7 @@ pub struct Tool {
 impl<T> From<T> for Tool
 where
     T: ExecutableTool + ToolDescription + NamedTool + Send + Sync + 'static,
-    T::Input: serde::de::DeserializeOwned + JsonSchema,
+    T::Input: serde::de::DeserializeOwned + JsonSchema + Send,
 {
     fn from(tool: T) -> Self {
         let definition = ToolDefinition::from(&tool);
@@ -6,7 +6,9 @@ use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::{NamedTool, ToolName, UsageParameterPrompt, UsagePrompt};
+use crate::{
+    CancellationToken, Error, NamedTool, ToolKind, ToolName, UsageParameterPrompt, UsagePrompt,
+};
 
 ///
 /// Refer to the specification over here:
@@ -18,6 +20,8 @@ pub struct ToolDefinition {
     pub description: String,
     pub input_schema: RootSchema,
     pub output_schema: Option<RootSchema>,
+    #[serde(default)]
+    pub kind: ToolKind,
 }
 
 impl ToolDefinition {
@@ -28,6 +32,7 @@ impl ToolDefinition {
             description: String::new(),
             input_schema: schemars::schema_for!(()), // Empty input schema
             output_schema: None,
+            kind: ToolKind::default(),
         }
     }
 
@@ -113,6 +118,7 @@ where
             description: full_description,
             input_schema: input,
             output_schema: Some(output),
+            kind: T::tool_kind(),
         }
     }
 }
@@ -126,4 +132,21 @@ pub trait ExecutableTool {
     type Input: DeserializeOwned;
 
     async fn call(&self, input: Self::Input) -> anyhow::Result<String>;
+
+    /// Executes the tool until it completes or the token is cancelled. Tools
+    /// that can stop gracefully, eg: to keep the output produced so far,
+    /// override this.
+    async fn call_cancellable(
+        &self,
+        input: Self::Input,
+        cancel: CancellationToken,
+    ) -> anyhow::Result<String>
+    where
+        Self::Input: Send,
+    {
+        tokio::select! {
+            result = self.call(input) => result,
+            _ = cancel.cancelled() => Err(Error::Cancelled.into()),
+        }
+    }
 }
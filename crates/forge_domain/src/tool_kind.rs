@@ -0,0 +1,30 @@
+use derive_more::derive::Display;
+use serde::{Deserialize, Serialize};
+
+/// Classifies a tool by the kind of side effects it can have.
+#[derive(Debug, Default, Display, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolKind {
+    /// Only reads information from the local system
+    #[display("read")]
+    Read,
+    /// Creates, modifies or removes files or other stored data
+    #[display("write")]
+    Write,
+    /// Executes arbitrary commands. Used for unclassified tools as it is the
+    /// most restrictive kind.
+    #[default]
+    #[display("exec")]
+    Execute,
+    /// Talks to external services over the network
+    #[display("network")]
+    Network,
+}
+
+impl ToolKind {
+    /// Read-only tools don't modify the local system, so other tool calls
+    /// can't observe their side effects and they are safe to run concurrently.
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, ToolKind::Read | ToolKind::Network)
+    }
+}
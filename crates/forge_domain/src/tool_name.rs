@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ToolKind;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct ToolName(String);
@@ -22,4 +24,5 @@ impl ToolName {
 
 pub trait NamedTool {
     fn tool_name() -> ToolName;
+    fn tool_kind() -> ToolKind;
 }
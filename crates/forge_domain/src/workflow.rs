@@ -1,10 +1,27 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{Agent, AgentId};
+use crate::{Agent, AgentId, ProviderConfig, ProviderId, ToolKind};
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Workflow {
     pub agents: Vec<Agent>,
+
+    /// Kinds of tools that can only be executed after the user approves the
+    /// call
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub require_approval: Vec<ToolKind>,
+
+    /// Providers that agents can select by name, in addition to the one
+    /// configured in the environment
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub providers: HashMap<ProviderId, ProviderConfig>,
+
+    /// Maximum amount of USD a conversation can spend. Requests to the models
+    /// are stopped once the cost of the conversation reaches it.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_cost: Option<f64>,
 }
 
 impl Workflow {
@@ -19,4 +36,10 @@ impl Workflow {
         self.find_agent(id)
             .ok_or_else(|| crate::Error::AgentUndefined(id.clone()))
     }
+
+    pub fn get_provider(&self, id: &ProviderId) -> crate::Result<&ProviderConfig> {
+        self.providers
+            .get(id)
+            .ok_or_else(|| crate::Error::ProviderUndefined(id.clone()))
+    }
 }
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use forge_app::EnvironmentService;
-use forge_domain::{Environment, Provider};
+use forge_domain::{Environment, Provider, Replay, ReplayMode, RetryConfig};
 
 pub struct ForgeEnvironmentService {
     restricted: bool,
@@ -34,13 +34,36 @@ impl ForgeEnvironmentService {
         dotenv::dotenv().ok();
         let cwd = std::env::current_dir().unwrap_or(PathBuf::from("."));
 
+        let replay = std::env::var("FORGE_REPLAY")
+            .ok()
+            .and_then(|mode| match mode.as_str() {
+                "record" => Some(ReplayMode::Record),
+                "replay" => Some(ReplayMode::Replay),
+                _ => None,
+            })
+            .map(|mode| Replay {
+                mode,
+                path: std::env::var("FORGE_REPLAY_PATH")
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| cwd.join(".forge").join("replay")),
+            });
+
+        // note: Replayed responses don't need a provider, so it can be left
+        // unconfigured.
+        let provider = Provider::from_env()
+            .or_else(|| {
+                replay
+                    .as_ref()
+                    .filter(|replay| replay.mode == ReplayMode::Replay)
+                    .map(|_| Provider::OpenRouter)
+            })
+            .expect("No API key found. Please set one of: FORGE_KEY, OPEN_ROUTER_KEY, OPENAI_API_KEY or ANTHROPIC_API_KEY, or FORGE_PROVIDER_URL for an OpenAI compatible server");
+        // note: OpenAI compatible servers can be used without a key.
         let provider_key = std::env::var("FORGE_KEY")
             .or_else(|_| std::env::var("OPEN_ROUTER_KEY"))
             .or_else(|_| std::env::var("OPENAI_API_KEY"))
             .or_else(|_| std::env::var("ANTHROPIC_API_KEY"))
-            .expect("No API key found. Please set one of: FORGE_KEY, OPEN_ROUTER_KEY, OPENAI_API_KEY or ANTHROPIC_API_KEY");
-        // note: since we know the key is set, we can unwrap here.
-        let provider = Provider::from_env().unwrap();
+            .unwrap_or_default();
         Environment {
             os: std::env::consts::OS.to_string(),
             pid: std::process::id(),
@@ -56,6 +79,12 @@ impl ForgeEnvironmentService {
             provider_key,
             provider_url: provider.to_base_url().to_string(),
             openai_key: std::env::var("OPENAI_API_KEY").ok(),
+            retry_config: std::env::var("FORGE_MAX_RETRIES")
+                .ok()
+                .and_then(|attempts| attempts.parse().ok())
+                .map(|attempts| RetryConfig::default().max_attempts(attempts))
+                .unwrap_or_default(),
+            replay,
         }
     }
 }
@@ -133,6 +162,21 @@ mod tests {
         assert_eq!(provider, Some(Provider::Anthropic));
     }
 
+    #[test]
+    #[serial]
+    fn test_provider_from_env_with_provider_url_and_without_key() {
+        reset_env();
+        env::set_var("FORGE_PROVIDER_URL", "http://localhost:11434/v1/");
+
+        let provider = Provider::from_env();
+        assert_eq!(
+            provider,
+            Some(Provider::OpenAICompatible(
+                "http://localhost:11434/v1/".to_string()
+            ))
+        );
+    }
+
     #[test]
     #[serial]
     fn test_provider_from_env_with_no_keys() {
@@ -156,7 +200,13 @@ mod tests {
             Provider::from_url("https://api.anthropic.com/v1/"),
             Some(Provider::Anthropic)
         );
-        assert_eq!(Provider::from_url("https://unknown.url/"), None);
+        assert_eq!(
+            Provider::from_url("http://localhost:11434/v1"),
+            Some(Provider::OpenAICompatible(
+                "http://localhost:11434/v1/".to_string()
+            ))
+        );
+        assert_eq!(Provider::from_url("unknown"), None);
     }
 
     #[test]
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use forge_api::{AgentMessage, ChatRequest, ChatResponse, ForgeAPI, ModelId, API};
+use tokio_stream::StreamExt;
+
+const WORKFLOW_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/test_workflow.yaml");
+const REPLAY_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/replay");
+
+/// The fixtures were recorded against a scripted OpenAI compatible server that
+/// serves this model, so the test covers the orchestrator's tool loop and cost
+/// tracking rather than the behavior of any real model.
+const MODEL: &str = "scripted/tool-loop";
+
+/// Runs the workflow on the replayed responses and returns every response of
+/// the chat
+async fn chat() -> Vec<ChatResponse> {
+    // note: Replaying is the default so the test runs offline. Record the
+    // fixtures again with `FORGE_REPLAY=record`.
+    if std::env::var("FORGE_REPLAY").is_err() {
+        std::env::set_var("FORGE_REPLAY", "replay");
+    }
+    if std::env::var("FORGE_REPLAY_PATH").is_err() {
+        std::env::set_var("FORGE_REPLAY_PATH", REPLAY_PATH);
+    }
+
+    // NOTE: In tests the CWD is not the project root
+    let api = ForgeAPI::init(true);
+    let mut workflow = api.load(Some(&PathBuf::from(WORKFLOW_PATH))).await.unwrap();
+    workflow.agents.iter_mut().for_each(|agent| {
+        agent.model = ModelId::new(MODEL);
+    });
+
+    let conversation_id = api.init(workflow).await.unwrap();
+    let request = ChatRequest::new(
+        "There is a cat hidden in the codebase. What is its name?",
+        conversation_id,
+    );
+
+    api.chat(request)
+        .await
+        .unwrap()
+        .map(|message| message.unwrap())
+        .map(|AgentMessage { message, .. }| message)
+        .collect::<Vec<_>>()
+        .await
+}
+
+#[tokio::test]
+async fn test_tool_loop_finds_cat_name() {
+    let responses = chat().await;
+
+    let tools = responses
+        .iter()
+        .filter_map(|response| match response {
+            ChatResponse::ToolCallEnd(result) if !result.is_error => {
+                Some(result.name.as_str().to_string())
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(tools, vec!["tool_forge_fs_search".to_string()]);
+
+    let text = responses
+        .iter()
+        .filter_map(|response| match response {
+            ChatResponse::Text(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<String>();
+    assert!(text.to_lowercase().contains("juniper"), "{text}");
+
+    // Both requests are priced from the replayed model list
+    let total = responses.iter().rev().find_map(|response| match response {
+        ChatResponse::Cost(cost) => Some(cost.total),
+        _ => None,
+    });
+    assert!(
+        total.is_some_and(|total| (total - 0.00208).abs() < 1e-9),
+        "{total:?}"
+    );
+}
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use forge_api::Image;
+
+/// Reads the images that the message mentions as `@path/to/image.png`. Paths
+/// are relative to `cwd`, mentions of other files are left to the model.
+pub async fn images(content: &str, cwd: &Path) -> Result<Vec<Image>> {
+    let mut images = Vec::new();
+    for word in content.split_whitespace() {
+        // Punctuation that ends the sentence isn't part of the path
+        let Some(path) = word
+            .strip_prefix('@')
+            .map(|path| path.trim_end_matches(['?', '!', ',', '.', ';', ':', ')']))
+            .map(Path::new)
+        else {
+            continue;
+        };
+        let Some(mime_type) = Image::mime_type_of(path) else {
+            continue;
+        };
+
+        let bytes = tokio::fs::read(cwd.join(path))
+            .await
+            .with_context(|| format!("Failed to attach image: {}", path.display()))?;
+        images.push(Image::new_bytes(bytes, mime_type));
+    }
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_images() {
+        let dir = tempfile::tempdir().unwrap();
+        tokio::fs::write(dir.path().join("screenshot.png"), b"png")
+            .await
+            .unwrap();
+
+        let actual = images(
+            "What's wrong in @screenshot.png? See @src/main.rs",
+            dir.path(),
+        )
+        .await
+        .unwrap();
+        let expected = vec![Image::new_bytes(b"png", "image/png")];
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_images_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let actual = images("Look at @missing.jpg", dir.path())
+            .await
+            .unwrap_err();
+        assert_eq!(actual.to_string(), "Failed to attach image: missing.jpg");
+    }
+}
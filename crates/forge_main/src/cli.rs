@@ -25,6 +25,12 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub verbose: bool,
 
+    /// Hide the reasoning of models that think before they answer.
+    ///
+    /// By default the reasoning is shown dimmed before the answer.
+    #[arg(long, default_value_t = false)]
+    pub hide_reasoning: bool,
+
     /// Enable restricted shell mode for enhanced security.
     ///
     /// Controls the shell execution environment:
@@ -44,4 +50,11 @@ pub struct Cli {
     /// Path to a file containing the workflow to execute.
     #[arg(long, short = 'w')]
     pub workflow: Option<PathBuf>,
+
+    /// Resume a previous conversation.
+    ///
+    /// Accepts either the ID of a stored conversation or the path to a
+    /// conversation dumped with the `/dump` command.
+    #[arg(long)]
+    pub resume: Option<String>,
 }
@@ -1,7 +1,7 @@
 use std::fmt;
 
 use colored::Colorize;
-use forge_api::{Environment, Usage};
+use forge_api::{Cost, Environment, Usage};
 
 pub enum Section {
     Title(String),
@@ -41,6 +41,17 @@ impl From<&Usage> for Info {
             .add_item("Prompt", usage.prompt_tokens)
             .add_item("Completion", usage.completion_tokens)
             .add_item("Total", usage.total_tokens)
+            .add_item("Cache Read", usage.cache_read_tokens)
+            .add_item("Cache Write", usage.cache_write_tokens)
+    }
+}
+
+impl From<&Cost> for Info {
+    fn from(cost: &Cost) -> Self {
+        Info::new()
+            .add_title("Cost".to_string())
+            .add_item("Turn", format!("${:.4}", cost.turn))
+            .add_item("Total", format!("${:.4}", cost.total))
     }
 }
 
@@ -56,6 +67,8 @@ impl From<&Environment> for Info {
             .add_item("Config", env.base_path.display())
             .add_item("Logs", env.log_path().display())
             .add_item("Database", env.db_path().display())
+            .add_item("Conversations", env.conversation_path().display())
+            .add_item("Checkpoints", env.checkpoint_path().display())
             .add_item("History", env.history_path().display())
     }
 }
@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
 use async_trait::async_trait;
-use forge_api::{Environment, Usage};
+use forge_api::{Approval, ApprovalRequest, Cost, Environment, Usage};
 use forge_display::TitleFormat;
 use tokio::fs;
 
@@ -54,19 +54,58 @@ impl UserInput for Console {
             }
         }
     }
+
+    async fn approve(&self, request: &ApprovalRequest) -> anyhow::Result<Approval> {
+        CONSOLE.writeln(
+            TitleFormat::execute(request.tool_call.name.as_str())
+                .sub_title(format!("{} tool, waiting for approval", request.kind))
+                .format(),
+        )?;
+        CONSOLE.writeln(format!("{}", request.tool_call.arguments))?;
+
+        loop {
+            let answer = read_line("Allow? [y]es / [a]lways / [n]o: ").await?;
+            match answer.to_lowercase().as_str() {
+                "y" | "yes" => return Ok(Approval::AllowOnce),
+                "a" | "always" => return Ok(Approval::AllowAlways),
+                "n" | "no" => {
+                    let reason = read_line("Reason (optional): ").await?;
+                    let reason = if reason.is_empty() {
+                        "No reason given".to_string()
+                    } else {
+                        reason
+                    };
+                    return Ok(Approval::Deny(reason));
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Reads a single line from the standard input without blocking the runtime
+async fn read_line(prompt: &str) -> anyhow::Result<String> {
+    CONSOLE.write(prompt)?;
+    let line = tokio::task::spawn_blocking(|| {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line).map(|_| line)
+    })
+    .await??;
+    Ok(line.trim().to_string())
 }
 
 pub enum PromptInput {
     Update {
         title: Option<String>,
         usage: Option<Usage>,
+        cost: Option<Cost>,
     },
 }
 
 impl From<PromptInput> for ForgePrompt {
     fn from(input: PromptInput) -> Self {
         match input {
-            PromptInput::Update { title, usage } => {
+            PromptInput::Update { title, usage, cost } => {
                 let mut prompt = ForgePrompt::default();
                 if let Some(title) = title {
                     prompt.title(title);
@@ -74,6 +113,9 @@ impl From<PromptInput> for ForgePrompt {
                 if let Some(usage) = usage {
                     prompt.usage(usage);
                 }
+                if let Some(cost) = cost {
+                    prompt.cost(cost);
+                }
                 prompt
             }
         }
@@ -1,3 +1,4 @@
+mod attachment;
 mod banner;
 mod cli;
 mod completer;
@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use forge_api::Model;
+use forge_api::{Approval, ApprovalRequest, Model};
 
 use crate::info::Info;
 
@@ -75,6 +75,22 @@ pub enum Command {
     Models,
     /// Dumps the current conversation into a json file
     Dump,
+    /// Lists the stored conversations along with their titles.
+    /// This can be triggered with the '/conversations' command.
+    Conversations,
+    /// Continues a previous conversation, identified either by its ID or by
+    /// the path of a dumped conversation. Without an argument the most
+    /// recently active conversation is resumed.
+    /// This can be triggered with the '/resume [id|path]' command.
+    Resume(Option<String>),
+    /// Lists the checkpoints of the current conversation along with the files
+    /// modified in each turn.
+    /// This can be triggered with the '/checkpoints' command.
+    Checkpoints,
+    /// Restores the files modified by tools to their state before the given
+    /// turn. Without an argument the latest checkpointed turn is undone.
+    /// This can be triggered with the '/undo [turn]' command.
+    Undo(Option<u64>),
 }
 
 impl Command {
@@ -91,6 +107,10 @@ impl Command {
             "/exit".to_string(),
             "/models".to_string(),
             "/dump".to_string(),
+            "/conversations".to_string(),
+            "/resume".to_string(),
+            "/checkpoints".to_string(),
+            "/undo".to_string(),
         ]
     }
 
@@ -113,11 +133,35 @@ impl Command {
             "/exit" => Command::Exit,
             "/models" => Command::Models,
             "/dump" => Command::Dump,
-            text => Command::Message(text.to_string()),
+            "/conversations" => Command::Conversations,
+            "/checkpoints" => Command::Checkpoints,
+            text => {
+                if let Some(target) = argument(text, "/resume") {
+                    return Command::Resume(target.map(|target| target.to_string()));
+                }
+
+                match argument(text, "/undo").map(|turn| turn.map(str::parse)) {
+                    Some(None) => Command::Undo(None),
+                    Some(Some(Ok(turn))) => Command::Undo(Some(turn)),
+                    _ => Command::Message(text.to_string()),
+                }
+            }
         }
     }
 }
 
+/// Extracts the optional argument of a command, or returns `None` if the text
+/// is not an invocation of the command.
+fn argument<'a>(text: &'a str, command: &str) -> Option<Option<&'a str>> {
+    match text.strip_prefix(command) {
+        Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => {
+            let rest = rest.trim();
+            Some((!rest.is_empty()).then_some(rest))
+        }
+        _ => None,
+    }
+}
+
 /// A trait for handling user input in the application.
 ///
 /// This trait defines the core functionality needed for processing
@@ -146,4 +190,50 @@ pub trait UserInput {
     /// * `Ok(Input)` - Successfully processed input
     /// * `Err` - An error occurred during input processing
     async fn prompt(&self, input: Option<Self::PromptInput>) -> anyhow::Result<Command>;
+
+    /// Asks the user whether a tool call is allowed to be executed.
+    ///
+    /// # Arguments
+    /// * `request` - The tool call that is waiting for approval
+    ///
+    /// # Returns
+    /// * `Ok(Approval)` - The user's decision
+    /// * `Err` - An error occurred while reading the answer
+    async fn approve(&self, request: &ApprovalRequest) -> anyhow::Result<Approval>;
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_resume() {
+        assert_eq!(Command::parse("/resume"), Command::Resume(None));
+        assert_eq!(
+            Command::parse("/resume  conversation.json "),
+            Command::Resume(Some("conversation.json".to_string()))
+        );
+        assert_eq!(
+            Command::parse("/resumeme"),
+            Command::Message("/resumeme".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_conversations() {
+        assert_eq!(Command::parse("/conversations"), Command::Conversations);
+    }
+
+    #[test]
+    fn test_parse_undo() {
+        assert_eq!(Command::parse("/undo"), Command::Undo(None));
+        assert_eq!(Command::parse("/undo 3"), Command::Undo(Some(3)));
+        assert_eq!(
+            Command::parse("/undo everything"),
+            Command::Message("/undo everything".to_string())
+        );
+        assert_eq!(Command::parse("/checkpoints"), Command::Checkpoints);
+    }
 }
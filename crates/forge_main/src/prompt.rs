@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 use derive_setters::Setters;
-use forge_api::Usage;
+use forge_api::{Cost, Usage};
 use nu_ansi_term::{Color, Style};
 use reedline::{Prompt, PromptHistorySearchStatus};
 
@@ -16,6 +16,7 @@ const RIGHT_CHEVRON: &str = "❯";
 pub struct ForgePrompt {
     title: Option<String>,
     usage: Option<Usage>,
+    cost: Option<Cost>,
 }
 
 impl Prompt for ForgePrompt {
@@ -33,10 +34,13 @@ impl Prompt for ForgePrompt {
 
     fn render_prompt_right(&self) -> Cow<str> {
         if let Some(usage) = self.usage.as_ref() {
-            let usage_text = format!(
+            let mut usage_text = format!(
                 "[{}/{}/{}]",
                 usage.prompt_tokens, usage.completion_tokens, usage.total_tokens
             );
+            if let Some(cost) = self.cost.as_ref() {
+                usage_text.push_str(&format!(" [${:.4}/${:.4}]", cost.turn, cost.total));
+            }
             Cow::Owned(
                 Style::new()
                     .bold()
@@ -101,7 +105,12 @@ mod tests {
 
     #[test]
     fn test_render_prompt_right_with_usage() {
-        let usage = Usage { prompt_tokens: 10, completion_tokens: 20, total_tokens: 30 };
+        let usage = Usage {
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            ..Default::default()
+        };
         let mut prompt = ForgePrompt::default();
         prompt.usage(usage);
         let usage_style = Style::new()
@@ -114,6 +123,25 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_render_prompt_right_with_cost() {
+        let mut prompt = ForgePrompt::default();
+        prompt.usage(Usage {
+            prompt_tokens: 10,
+            completion_tokens: 20,
+            total_tokens: 30,
+            ..Default::default()
+        });
+        prompt.cost(Cost { turn: 0.0012, total: 0.25 });
+        let actual = prompt.render_prompt_right();
+        let expected = Style::new()
+            .bold()
+            .fg(Color::DarkGray)
+            .paint("[10/20/30] [$0.0012/$0.2500]")
+            .to_string();
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_render_prompt_right_without_usage() {
         let prompt = ForgePrompt::default();
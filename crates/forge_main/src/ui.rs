@@ -3,19 +3,20 @@ use std::sync::Arc;
 use anyhow::Result;
 use colored::Colorize;
 use forge_api::{
-    AgentMessage, ChatRequest, ChatResponse, ConversationId, EventType, Model, Usage, API,
+    AgentMessage, Approval, ChatRequest, ChatResponse, Conversation, ConversationId, Cost,
+    EventType, Model, MpscStream, Usage, API,
 };
 use forge_display::TitleFormat;
 use forge_tracker::EventKind;
 use lazy_static::lazy_static;
 use tokio_stream::StreamExt;
 
-use crate::banner;
 use crate::cli::Cli;
 use crate::console::CONSOLE;
 use crate::info::Info;
 use crate::input::{Console, PromptInput};
 use crate::model::{Command, UserInput};
+use crate::{attachment, banner};
 
 lazy_static! {
     pub static ref TRACKER: forge_tracker::Tracker = forge_tracker::Tracker::default();
@@ -26,6 +27,9 @@ struct UIState {
     current_title: Option<String>,
     conversation_id: Option<ConversationId>,
     usage: Usage,
+    cost: Option<Cost>,
+    /// Whether the last printed text was the model's reasoning
+    reasoning: bool,
 }
 
 impl From<&UIState> for PromptInput {
@@ -33,6 +37,7 @@ impl From<&UIState> for PromptInput {
         PromptInput::Update {
             title: state.current_title.clone(),
             usage: Some(state.usage.clone()),
+            cost: state.cost.clone(),
         }
     }
 }
@@ -63,6 +68,11 @@ impl<F: API> UI<F> {
     }
 
     pub async fn run(&mut self) -> Result<()> {
+        // Resume a previous conversation if requested
+        if let Some(target) = self.cli.resume.clone() {
+            self.handle_resume(Some(target)).await?;
+        }
+
         // Handle direct prompt if provided
         let prompt = self.cli.prompt.clone();
         if let Some(prompt) = prompt {
@@ -87,6 +97,36 @@ impl<F: API> UI<F> {
                     input = self.console.prompt(prompt_input).await?;
                     continue;
                 }
+                Command::Conversations => {
+                    self.handle_conversations().await?;
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Resume(ref target) => {
+                    if let Err(err) = self.handle_resume(target.clone()).await {
+                        CONSOLE.writeln(TitleFormat::failed(format!("{:?}", err)).format())?;
+                    }
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Checkpoints => {
+                    if let Err(err) = self.handle_checkpoints().await {
+                        CONSOLE.writeln(TitleFormat::failed(format!("{:?}", err)).format())?;
+                    }
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
+                Command::Undo(turn) => {
+                    if let Err(err) = self.handle_undo(turn).await {
+                        CONSOLE.writeln(TitleFormat::failed(format!("{:?}", err)).format())?;
+                    }
+                    let prompt_input = Some((&self.state).into());
+                    input = self.console.prompt(prompt_input).await?;
+                    continue;
+                }
                 Command::New => {
                     banner::display()?;
                     self.state = Default::default();
@@ -95,8 +135,11 @@ impl<F: API> UI<F> {
                     continue;
                 }
                 Command::Info => {
-                    let info =
+                    let mut info =
                         Info::from(&self.api.environment()).extend(Info::from(&self.state.usage));
+                    if let Some(cost) = &self.state.cost {
+                        info = info.extend(Info::from(cost));
+                    }
 
                     CONSOLE.writeln(info.to_string())?;
 
@@ -151,7 +194,8 @@ impl<F: API> UI<F> {
             }
         };
 
-        let chat = ChatRequest { content: content.clone(), conversation_id };
+        let images = attachment::images(&content, &self.api.environment().cwd).await?;
+        let chat = ChatRequest::new(content.clone(), conversation_id).images(images);
 
         tokio::spawn(TRACKER.dispatch(EventKind::Prompt(content)));
         match self.api.chat(chat).await {
@@ -162,16 +206,41 @@ impl<F: API> UI<F> {
 
     async fn handle_chat_stream(
         &mut self,
-        stream: &mut (impl StreamExt<Item = Result<AgentMessage<ChatResponse>>> + Unpin),
+        stream: &mut MpscStream<Result<AgentMessage<ChatResponse>>>,
     ) -> Result<()> {
+        let mut cancelled = false;
         loop {
             tokio::select! {
                 _ = tokio::signal::ctrl_c() => {
-                    return Ok(());
+                    // A second interrupt stops waiting for the turn to wind down
+                    if cancelled {
+                        return Ok(());
+                    }
+
+                    cancelled = true;
+                    cancel(stream)?;
                 }
                 maybe_message = stream.next() => {
                     match maybe_message {
-                        Some(Ok(message)) => self.handle_chat_response(message)?,
+                        Some(Ok(AgentMessage {
+                            message: ChatResponse::ApprovalRequest(request), ..
+                        })) => {
+                            // The prompt waits on stdin, so an interrupt has to be awaited
+                            // alongside it
+                            tokio::select! {
+                                approval = self.console.approve(&request) => {
+                                    request.respond(approval?);
+                                }
+                                _ = tokio::signal::ctrl_c() => {
+                                    request.respond(Approval::Deny(
+                                        "Cancelled by the user".to_string(),
+                                    ));
+                                    cancelled = true;
+                                    cancel(stream)?;
+                                }
+                            }
+                        }
+                        Some(Ok(message)) => self.handle_chat_response(message).await?,
                         Some(Err(err)) => {
                             return Err(err);
                         }
@@ -217,16 +286,165 @@ impl<F: API> UI<F> {
         Ok(())
     }
 
-    fn handle_chat_response(&mut self, message: AgentMessage<ChatResponse>) -> Result<()> {
+    async fn handle_conversations(&mut self) -> Result<()> {
+        let conversations = self.api.conversations().await?;
+        if conversations.is_empty() {
+            CONSOLE.writeln(
+                TitleFormat::failed("conversations")
+                    .error("none found")
+                    .format(),
+            )?;
+            return Ok(());
+        }
+
+        let info = conversations.iter().fold(
+            Info::new().add_title("Conversations"),
+            |info, conversation| {
+                let title = conversation
+                    .title()
+                    .unwrap_or_else(|| "Untitled".to_string());
+                let updated_at = conversation.updated_at().unwrap_or_default();
+                info.add_item(conversation.id.clone(), format!("{title} {updated_at}"))
+            },
+        );
+
+        CONSOLE.writeln(info.to_string())?;
+        Ok(())
+    }
+
+    async fn handle_checkpoints(&mut self) -> Result<()> {
+        let checkpoints = match self.state.conversation_id {
+            Some(ref conversation_id) => self.api.checkpoints(conversation_id).await?,
+            None => Vec::new(),
+        };
+        if checkpoints.is_empty() {
+            CONSOLE.writeln(
+                TitleFormat::failed("checkpoints")
+                    .error("none found")
+                    .format(),
+            )?;
+            return Ok(());
+        }
+
+        let info =
+            checkpoints
+                .iter()
+                .fold(Info::new().add_title("Checkpoints"), |info, checkpoint| {
+                    checkpoint.files.iter().fold(info, |info, file| {
+                        info.add_item(format!("turn {}", checkpoint.turn), file.display())
+                    })
+                });
+
+        CONSOLE.writeln(info.to_string())?;
+        Ok(())
+    }
+
+    /// Rolls the files back to their state before the given turn. When no
+    /// turn is given, the latest checkpointed turn is undone.
+    async fn handle_undo(&mut self, turn: Option<u64>) -> Result<()> {
+        let conversation_id = self
+            .state
+            .conversation_id
+            .clone()
+            .ok_or(anyhow::anyhow!("No conversation has been started yet"))?;
+
+        let turn = match turn {
+            Some(turn) => turn,
+            None => match self.api.checkpoints(&conversation_id).await?.last() {
+                Some(checkpoint) => checkpoint.turn,
+                None => {
+                    CONSOLE.writeln(
+                        TitleFormat::failed("undo")
+                            .error("nothing to undo")
+                            .format(),
+                    )?;
+                    return Ok(());
+                }
+            },
+        };
+
+        let files = self.api.undo(&conversation_id, turn).await?;
+        for file in files.iter() {
+            CONSOLE.writeln(format!("{}", file.display().to_string().dimmed()))?;
+        }
+
+        CONSOLE.writeln(
+            TitleFormat::success("undo")
+                .sub_title(format!(
+                    "restored {} files to before turn {}",
+                    files.len(),
+                    turn
+                ))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    /// Restores a conversation from its ID or from a dumped conversation file.
+    /// When no target is given, the most recently active conversation is
+    /// resumed.
+    async fn handle_resume(&mut self, target: Option<String>) -> Result<()> {
+        let conversation = match target {
+            None => self.api.conversations().await?.into_iter().next(),
+            Some(target) => match ConversationId::parse(&target) {
+                Ok(conversation_id) => self.api.conversation(&conversation_id).await?,
+                Err(_) => {
+                    let content = tokio::fs::read_to_string(&target).await?;
+                    let conversation: Conversation = serde_json::from_str(&content)?;
+                    self.api.upsert_conversation(conversation.clone()).await?;
+                    Some(conversation)
+                }
+            },
+        };
+
+        let Some(conversation) = conversation else {
+            CONSOLE.writeln(
+                TitleFormat::failed("resume")
+                    .error("conversation not found")
+                    .format(),
+            )?;
+            return Ok(());
+        };
+
+        self.state = UIState {
+            current_title: conversation.title(),
+            conversation_id: Some(conversation.id.clone()),
+            usage: Default::default(),
+            cost: (conversation.cost > 0.0).then_some(Cost { turn: 0.0, total: conversation.cost }),
+            reasoning: false,
+        };
+
+        CONSOLE.writeln(
+            TitleFormat::success("resume")
+                .sub_title(format!("conversation_id: {}", conversation.id))
+                .format(),
+        )?;
+        Ok(())
+    }
+
+    async fn handle_chat_response(&mut self, message: AgentMessage<ChatResponse>) -> Result<()> {
         match message.message {
             ChatResponse::Text(text) => {
                 // Any agent that ends with "worker" is considered a worker agent.
                 // Worker agents don't print anything to the console.
                 if !message.agent.as_str().to_lowercase().ends_with("worker") {
+                    if std::mem::take(&mut self.state.reasoning) {
+                        CONSOLE.newline()?;
+                        CONSOLE.newline()?;
+                    }
                     CONSOLE.write(&text)?;
                 }
             }
+            ChatResponse::Reasoning(text) => {
+                if !self.cli.hide_reasoning
+                    && !message.agent.as_str().to_lowercase().ends_with("worker")
+                {
+                    self.state.reasoning = true;
+                    CONSOLE.write(text.dimmed().to_string())?;
+                }
+            }
             ChatResponse::ToolCallStart(_) => {
+                self.state.reasoning = false;
                 CONSOLE.newline()?;
                 CONSOLE.newline()?;
             }
@@ -261,7 +479,44 @@ impl<F: API> UI<F> {
             ChatResponse::Usage(u) => {
                 self.state.usage = u;
             }
+            ChatResponse::Cost(cost) => {
+                self.state.cost = Some(cost);
+            }
+            ChatResponse::ContextCompacted(compaction) => {
+                CONSOLE.writeln(
+                    TitleFormat::execute("compact")
+                        .sub_title(compaction.to_string())
+                        .format(),
+                )?;
+            }
+            ChatResponse::Retry(retry) => {
+                CONSOLE.writeln(
+                    TitleFormat::failed("retry")
+                        .sub_title(retry.to_string())
+                        .format(),
+                )?;
+            }
+            ChatResponse::ModelSwitched(switch) => {
+                CONSOLE.writeln(
+                    TitleFormat::failed("fallback")
+                        .sub_title(switch.to_string())
+                        .format(),
+                )?;
+            }
+            // Answered while handling the stream, which can cancel the turn
+            ChatResponse::ApprovalRequest(_) => {}
         }
         Ok(())
     }
 }
+
+/// Stops the current turn of the chat
+fn cancel(stream: &mut MpscStream<Result<AgentMessage<ChatResponse>>>) -> Result<()> {
+    stream.cancel();
+    CONSOLE.writeln(
+        TitleFormat::failed("cancel")
+            .sub_title("stopping the current turn")
+            .format(),
+    )?;
+    Ok(())
+}
@@ -2,14 +2,16 @@ use anyhow::Context as _;
 use derive_setters::Setters;
 use forge_domain::{
     ChatCompletionMessage, Context, Model, ModelId, Parameters, ProviderService, ResultStream,
+    RetryConfig, Usage,
 };
 use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::{Client, Url};
-use reqwest_eventsource::{Event, RequestBuilderExt};
+use reqwest_eventsource::Event;
 use tokio_stream::StreamExt;
 
 use super::request::Request;
-use super::response::{EventData, ListModelResponse};
+use super::response::{Event as AnthropicEvent, EventData, ListModelResponse};
+use crate::retry::{event_stream, unavailable, Attempt};
 
 #[derive(Debug, Default, Clone, Setters)]
 #[setters(into, strip_option)]
@@ -17,6 +19,7 @@ pub struct AnthropicBuilder {
     api_key: Option<String>,
     base_url: Option<String>,
     anthropic_version: Option<String>,
+    retry_config: Option<RetryConfig>,
 }
 
 impl AnthropicBuilder {
@@ -36,7 +39,13 @@ impl AnthropicBuilder {
             .api_key
             .ok_or_else(|| anyhow::anyhow!("API key is required"))?;
 
-        Ok(Anthropic { client, base_url, api_key, anthropic_version })
+        Ok(Anthropic {
+            client,
+            base_url,
+            api_key,
+            anthropic_version,
+            retry_config: self.retry_config.unwrap_or_default(),
+        })
     }
 }
 
@@ -46,6 +55,7 @@ pub struct Anthropic {
     api_key: String,
     base_url: Url,
     anthropic_version: String,
+    retry_config: RetryConfig,
 }
 
 impl Anthropic {
@@ -93,22 +103,45 @@ impl ProviderService for Anthropic {
         context: Context,
     ) -> ResultStream<ChatCompletionMessage, anyhow::Error> {
         // TODO: depending on model, we've to set the max_tokens for request. for now,
-        // we're setting it to 4000.
+        // we're setting it to 4000 unless the agent sets it, on top of the thinking
+        // budget, which counts towards the limit.
+        let budget_tokens = context
+            .thinking
+            .as_ref()
+            .map(|thinking| thinking.budget_tokens)
+            .unwrap_or_default();
+        let max_tokens = context
+            .sampling
+            .as_ref()
+            .and_then(|sampling| sampling.max_tokens)
+            .map_or(4000u64, u64::from);
         let request = Request::try_from(context)?
             .model(id.to_string())
             .stream(true)
-            .max_tokens(4000u64);
+            .max_tokens(max_tokens + budget_tokens)
+            .set_cache();
 
-        let es = self
+        let request = self
             .client
             .post(self.url("/messages")?)
             .headers(self.headers())
-            .json(&request)
-            .eventsource()?;
+            .json(&request);
 
-        let stream = es
-            .take_while(|message| !matches!(message, Err(reqwest_eventsource::Error::StreamEnded)))
-            .then(|event| async {
+        let stream = event_stream(self.retry_config.clone(), request)?
+            .take_while(|attempt| {
+                !matches!(
+                    attempt,
+                    Attempt::Event(Err(reqwest_eventsource::Error::StreamEnded))
+                )
+            })
+            .then(|attempt| async {
+                let event = match attempt {
+                    Attempt::Retry(retry) => {
+                        return Some(Ok(ChatCompletionMessage::default().retry(retry)))
+                    }
+                    Attempt::Exhausted(error) => return Some(Err(unavailable(error).await)),
+                    Attempt::Event(event) => event,
+                };
                 match event {
                     Ok(event) => match event {
                         Event::Open => None,
@@ -125,11 +158,36 @@ impl ProviderService for Anthropic {
                         ),
                     },
                     Err(reqwest_eventsource::Error::StreamEnded) => None,
+                    // The body of failed requests describes the error in the same format as
+                    // error events
+                    Err(reqwest_eventsource::Error::InvalidStatusCode(status, response)) => {
+                        let body = response.text().await.unwrap_or_default();
+                        Some(match serde_json::from_str::<EventData>(&body) {
+                            Ok(EventData::KnownEvent(event @ AnthropicEvent::Error { .. })) => {
+                                ChatCompletionMessage::try_from(event)
+                            }
+                            _ => Err(anyhow::anyhow!("Anthropic API error: {status}: {body}")),
+                        })
+                    }
                     Err(err) => Some(Err(err.into())),
                 }
             });
 
-        Ok(Box::pin(stream.filter_map(|x| x)))
+        // note: The prompt tokens are reported when the message starts and the
+        // completion tokens when it ends, so every reported usage includes the
+        // values seen before.
+        let mut usage = Usage::default();
+        let stream = stream.filter_map(|x| x).map(move |message| {
+            message.map(|message| match message.usage.clone() {
+                Some(next) => {
+                    usage = merge_usage(&usage, next);
+                    message.usage(usage.clone())
+                }
+                None => message,
+            })
+        });
+
+        Ok(Box::pin(stream))
     }
     async fn models(&self) -> anyhow::Result<Vec<Model>> {
         let text = self
@@ -148,14 +206,31 @@ impl ProviderService for Anthropic {
     async fn parameters(&self, _model: &ModelId) -> anyhow::Result<Parameters> {
         // TODO: anthropic provider doesn't have this API, so for now allowing tool
         // calls for all models.
-        Ok(Parameters { tool_supported: true })
+        // note: Anthropic accepts temperatures from 0 to 1 rather than OpenAI's 0 to 2.
+        // ref: https://docs.anthropic.com/en/api/messages#body-temperature
+        Ok(Parameters { max_temperature: Some(1.0), ..Parameters::new(true) })
+    }
+}
+
+/// Anthropic's usage counts are cumulative, so the larger value of each field
+/// is the most recent one
+fn merge_usage(current: &Usage, next: Usage) -> Usage {
+    let prompt_tokens = current.prompt_tokens.max(next.prompt_tokens);
+    let completion_tokens = current.completion_tokens.max(next.completion_tokens);
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+        cache_read_tokens: current.cache_read_tokens.max(next.cache_read_tokens),
+        cache_write_tokens: current.cache_write_tokens.max(next.cache_write_tokens),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use forge_domain::{
-        Context, ContextMessage, ToolCallFull, ToolCallId, ToolChoice, ToolName, ToolResult,
+        Context, ContextMessage, Image, OutputSchema, Reasoning, Sampling, Thinking, ToolCallFull,
+        ToolCallId, ToolChoice, ToolDefinition, ToolName, ToolResult,
     };
 
     use super::*;
@@ -198,4 +273,144 @@ mod tests {
             .max_tokens(4000u64);
         insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
     }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_cache() {
+        let context = Context::default()
+            .add_message(ContextMessage::system("You're expert at math."))
+            .add_message(ContextMessage::user("what's 2 + 2 ?"))
+            .add_message(ContextMessage::assistant("4", None))
+            .add_message(ContextMessage::user("and 3 + 3 ?"))
+            .add_tool(ToolDefinition::new("math").description("Evaluates an expression"))
+            .add_tool(ToolDefinition::new("plot").description("Plots a function"));
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("sonnet-3.5".to_string())
+            .max_tokens(4000u64)
+            .set_cache();
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
+
+    #[test]
+    fn test_merge_usage() {
+        let start = Usage {
+            prompt_tokens: 1250,
+            completion_tokens: 1,
+            total_tokens: 1251,
+            cache_read_tokens: 1000,
+            cache_write_tokens: 200,
+        };
+        let delta = Usage {
+            completion_tokens: 42,
+            total_tokens: 42,
+            ..Default::default()
+        };
+
+        let actual = merge_usage(&merge_usage(&Usage::default(), start), delta);
+        let expected = Usage {
+            prompt_tokens: 1250,
+            completion_tokens: 42,
+            total_tokens: 1292,
+            cache_read_tokens: 1000,
+            cache_write_tokens: 200,
+        };
+        assert_eq!(actual, expected);
+    }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_thinking() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("what's 2 + 2 ?"))
+            .add_message(
+                ContextMessage::assistant(
+                    "",
+                    Some(vec![ToolCallFull {
+                        name: ToolName::new("math"),
+                        call_id: Some(ToolCallId::new("math-1")),
+                        arguments: serde_json::json!({"expression": "2 + 2"}),
+                    }]),
+                )
+                .with_reasoning(vec![
+                    Reasoning::Thinking {
+                        text: "I should use the math tool.".to_string(),
+                        signature: Some("EqQBCgIYAhIM".to_string()),
+                    },
+                    Reasoning::Thinking { text: "Interrupted".to_string(), signature: None },
+                    Reasoning::Redacted { data: "EmwKAhgBEgy3va3pzix".to_string() },
+                ]),
+            )
+            .tool_choice(ToolChoice::Call(ToolName::new("math")))
+            .thinking(Thinking { budget_tokens: 2048 });
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("claude-3-7-sonnet".to_string())
+            .max_tokens(6048u64);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_images() {
+        let context = Context::default().add_message(
+            ContextMessage::user("What's in this screenshot?")
+                .with_images(vec![Image::new_bytes(b"png", "image/png")]),
+        );
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("sonnet-3.5".to_string())
+            .max_tokens(4000u64);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_output_schema() {
+        let context = Context::default()
+            .add_message(ContextMessage::system("You generate titles."))
+            .add_message(ContextMessage::user("Fix the login page"))
+            .output_schema(OutputSchema::new(serde_json::json!({
+                "type": "object",
+                "properties": {"title": {"type": "string"}},
+                "required": ["title"]
+            })));
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("sonnet-3.5".to_string())
+            .max_tokens(4000u64);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_sampling() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("Write a haiku"))
+            .sampling(Sampling {
+                temperature: Some(0.7),
+                top_k: Some(40),
+                stop: vec!["---".to_string()],
+                ..Default::default()
+            });
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("sonnet-3.5".to_string())
+            .max_tokens(4000u64);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_request_conversion_with_sampling_and_thinking() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("Write a haiku"))
+            .thinking(Thinking { budget_tokens: 2048 })
+            .sampling(Sampling {
+                temperature: Some(0.7),
+                top_p: Some(0.5),
+                top_k: Some(40),
+                stop: vec!["---".to_string()],
+                ..Default::default()
+            });
+        let request = Request::try_from(context)
+            .unwrap()
+            .model("claude-3-7-sonnet".to_string())
+            .max_tokens(6048u64);
+        insta::assert_snapshot!(serde_json::to_string_pretty(&request).unwrap());
+    }
 }
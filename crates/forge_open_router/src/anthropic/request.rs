@@ -1,7 +1,12 @@
 use derive_setters::Setters;
-use forge_domain::ContextMessage;
+use forge_domain::{ContextMessage, Reasoning};
 use serde::{Deserialize, Serialize};
 
+/// Number of trailing messages that get a cache breakpoint. Together with the
+/// tool definitions and the system prompt, this uses all four breakpoints that
+/// Anthropic allows per request.
+const CACHED_MESSAGES: usize = 2;
+
 #[derive(Serialize, Default, Setters)]
 #[setters(into, strip_option)]
 pub struct Request {
@@ -10,15 +15,18 @@ pub struct Request {
     model: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     metadata: Option<Metadata>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    stop_sequence: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    #[setters(skip)]
+    system: Option<System>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<Thinking>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     tool_choice: Option<ToolChoice>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tools: Vec<ToolDefinition>,
@@ -34,7 +42,7 @@ impl TryFrom<forge_domain::Context> for Request {
         // note: Anthropic only supports 1 system message in context, so from the
         // context we pick the first system message available.
         // ref: https://docs.anthropic.com/en/api/messages#body-system
-        let system = request.messages.iter().find_map(|message| {
+        let mut system = request.messages.iter().find_map(|message| {
             if let ContextMessage::ContentMessage(chat_message) = message {
                 if chat_message.role == forge_domain::Role::System {
                     Some(chat_message.content.clone())
@@ -46,6 +54,25 @@ impl TryFrom<forge_domain::Context> for Request {
             }
         });
 
+        // note: Anthropic doesn't have a structured output mode, so the schema is
+        // described in the system prompt instead.
+        if let Some(output_schema) = &request.output_schema {
+            let instruction = output_schema.instruction();
+            system = Some(match system {
+                Some(system) => format!("{system}\n\n{instruction}"),
+                None => instruction,
+            });
+        }
+
+        // note: Extended thinking doesn't allow changing the temperature or top_k, and
+        // only allows top_p from 0.95 up.
+        // ref: https://docs.anthropic.com/en/docs/build-with-claude/extended-thinking
+        let mut sampling = request.sampling.unwrap_or_default();
+        if request.thinking.is_some() {
+            sampling.temperature = None;
+            sampling.top_k = None;
+            sampling.top_p = sampling.top_p.filter(|top_p| *top_p >= 0.95);
+        }
         Ok(Self {
             messages: request
                 .messages
@@ -65,13 +92,62 @@ impl TryFrom<forge_domain::Context> for Request {
                 .into_iter()
                 .map(ToolDefinition::try_from)
                 .collect::<std::result::Result<Vec<_>, _>>()?,
-            system,
-            tool_choice: request.tool_choice.map(ToolChoice::from),
+            system: system.map(System::Text),
+            tool_choice: match request.thinking {
+                // note: Forcing the use of a tool isn't supported with extended thinking.
+                // ref: https://docs.anthropic.com/en/docs/build-with-claude/extended-thinking
+                Some(_) => request
+                    .tool_choice
+                    .map(|_| ToolChoice::Auto { disable_parallel_tool_use: None }),
+                None => request.tool_choice.map(ToolChoice::from),
+            },
+            thinking: request.thinking.map(Thinking::from),
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            top_k: sampling.top_k.map(u64::from),
+            stop_sequences: sampling.stop,
             ..Default::default()
         })
     }
 }
 
+impl Request {
+    /// Places prompt cache breakpoints on the tool definitions, the system
+    /// prompt and the last messages, so that the next request can read the
+    /// unchanged beginning of the conversation from the cache.
+    // ref: https://docs.anthropic.com/en/docs/build-with-claude/prompt-caching
+    pub fn set_cache(mut self) -> Self {
+        if let Some(tool) = self.tools.last_mut() {
+            tool.cache_control = Some(CacheControl::Ephemeral);
+        }
+
+        self.system = self.system.map(|system| match system {
+            System::Text(text) => System::Blocks(vec![Content::Text {
+                text,
+                cache_control: Some(CacheControl::Ephemeral),
+            }]),
+            system => system,
+        });
+
+        for message in self.messages.iter_mut().rev().take(CACHED_MESSAGES) {
+            if let Some(content) = message.content.last_mut() {
+                content.set_cache();
+            }
+        }
+
+        self
+    }
+}
+
+/// The system prompt is sent as plain text, unless it's split into blocks to
+/// be cached
+#[derive(Serialize)]
+#[serde(untagged)]
+enum System {
+    Text(String),
+    Blocks(Vec<Content>),
+}
+
 #[derive(Serialize)]
 pub struct Metadata {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -98,6 +174,32 @@ impl TryFrom<ContextMessage> for Message {
                         + 1,
                 );
 
+                // note: Thinking blocks have to precede the rest of the content. Blocks without
+                // a signature were interrupted and can't be verified, so they're left out.
+                for reasoning in chat_message.reasoning.into_iter().flatten() {
+                    match reasoning {
+                        Reasoning::Thinking { text, signature: Some(signature) } => {
+                            content.push(Content::Thinking { thinking: text, signature })
+                        }
+                        Reasoning::Thinking { signature: None, .. } => {}
+                        Reasoning::Redacted { data } => {
+                            content.push(Content::RedactedThinking { data })
+                        }
+                    }
+                }
+
+                // note: Images are placed before the text, which works best according to the
+                // Anthropic docs.
+                for image in chat_message.images.into_iter().flatten() {
+                    content.push(Content::Image {
+                        source: ImageSource::Base64 {
+                            media_type: image.mime_type().to_string(),
+                            data: image.data().to_string(),
+                        },
+                        cache_control: None,
+                    });
+                }
+
                 if !chat_message.content.is_empty() {
                     // note: Anthropic does not allow empty text content.
                     content.push(Content::Text { text: chat_message.content, cache_control: None });
@@ -127,6 +229,18 @@ impl TryFrom<ContextMessage> for Message {
 #[derive(Serialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 enum Content {
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
+    Image {
+        source: ImageSource,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
     Text {
         text: String,
         #[serde(skip_serializing_if = "Option::is_none")]
@@ -150,6 +264,21 @@ enum Content {
     },
 }
 
+impl Content {
+    fn set_cache(&mut self) {
+        match self {
+            Content::Image { cache_control, .. }
+            | Content::Text { cache_control, .. }
+            | Content::ToolUse { cache_control, .. }
+            | Content::ToolResult { cache_control, .. } => {
+                *cache_control = Some(CacheControl::Ephemeral)
+            }
+            // note: Thinking blocks can't be cached directly.
+            Content::Thinking { .. } | Content::RedactedThinking { .. } => {}
+        }
+    }
+}
+
 impl TryFrom<forge_domain::ToolCallFull> for Content {
     type Error = anyhow::Error;
     fn try_from(value: forge_domain::ToolCallFull) -> std::result::Result<Self, Self::Error> {
@@ -184,8 +313,13 @@ impl TryFrom<forge_domain::ToolResult> for Content {
 }
 
 #[derive(Serialize)]
-#[serde(rename_all = "snake_case")]
-#[allow(dead_code)]
+#[serde(rename_all = "snake_case", tag = "type")]
+enum ImageSource {
+    Base64 { media_type: String, data: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
 pub enum CacheControl {
     Ephemeral,
 }
@@ -232,6 +366,18 @@ impl From<forge_domain::ToolChoice> for ToolChoice {
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Thinking {
+    Enabled { budget_tokens: u64 },
+}
+
+impl From<forge_domain::Thinking> for Thinking {
+    fn from(value: forge_domain::Thinking) -> Self {
+        Thinking::Enabled { budget_tokens: value.budget_tokens }
+    }
+}
+
 #[derive(Serialize)]
 pub struct ToolDefinition {
     name: String,
@@ -1,6 +1,8 @@
 use std::fmt::{self, Display, Formatter};
 
-use forge_domain::{ChatCompletionMessage, Content, ModelId, ToolCallId, ToolCallPart, ToolName};
+use forge_domain::{
+    ChatCompletionMessage, Content, ModelId, ReasoningPart, ToolCallId, ToolCallPart, ToolName,
+};
 use serde::Deserialize;
 
 use super::request::Role;
@@ -23,6 +25,8 @@ impl From<Model> for forge_domain::Model {
             name: value.display_name,
             description: None,
             context_length: None,
+            pricing: None,
+            image_input: None,
         }
     }
 }
@@ -43,6 +47,26 @@ pub struct MessageStart {
 pub struct Usage {
     pub input_tokens: Option<u64>,
     pub output_tokens: Option<u64>,
+    pub cache_creation_input_tokens: Option<u64>,
+    pub cache_read_input_tokens: Option<u64>,
+}
+
+impl From<Usage> for forge_domain::Usage {
+    fn from(value: Usage) -> Self {
+        let cache_read_tokens = value.cache_read_input_tokens.unwrap_or_default();
+        let cache_write_tokens = value.cache_creation_input_tokens.unwrap_or_default();
+        // note: Anthropic doesn't count the cached tokens as input tokens.
+        let prompt_tokens =
+            value.input_tokens.unwrap_or_default() + cache_read_tokens + cache_write_tokens;
+        let completion_tokens = value.output_tokens.unwrap_or_default();
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            cache_read_tokens,
+            cache_write_tokens,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -106,12 +130,16 @@ pub enum EventData {
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ErrorData {
     OverloadedError { message: String },
+    InvalidRequestError { message: String },
 }
 
 impl Display for ErrorData {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             ErrorData::OverloadedError { message } => write!(f, "OverloadedError: {}", message),
+            ErrorData::InvalidRequestError { message } => {
+                write!(f, "InvalidRequestError: {}", message)
+            }
         }
     }
 }
@@ -140,6 +168,18 @@ pub enum ContentBlock {
     InputJsonDelta {
         partial_json: String,
     },
+    Thinking {
+        thinking: String,
+    },
+    ThinkingDelta {
+        thinking: String,
+    },
+    SignatureDelta {
+        signature: String,
+    },
+    RedactedThinking {
+        data: String,
+    },
 }
 
 impl TryFrom<EventData> for ChatCompletionMessage {
@@ -163,8 +203,24 @@ impl TryFrom<Event> for ChatCompletionMessage {
             | Event::ContentBlockDelta { delta: content_block, .. } => {
                 ChatCompletionMessage::try_from(content_block)?
             }
-            Event::MessageDelta { delta, .. } => {
-                ChatCompletionMessage::assistant(Content::part("")).finish_reason(delta.stop_reason)
+            Event::MessageStart { message } => {
+                ChatCompletionMessage::assistant(Content::part("")).usage(message.usage)
+            }
+            Event::MessageDelta { delta, usage } => {
+                ChatCompletionMessage::assistant(Content::part(""))
+                    .finish_reason(delta.stop_reason)
+                    .usage(usage)
+            }
+            Event::Error { error: ErrorData::InvalidRequestError { message } }
+                if forge_domain::Error::context_length_exceeded(&message).is_some() =>
+            {
+                return Err(forge_domain::Error::ContextLengthExceeded(message).into());
+            }
+            // note: Anthropic reports an overloaded API in the stream rather than with a
+            // status code, so the request is handed to the fallback models here.
+            // ref: https://docs.anthropic.com/en/api/errors
+            Event::Error { error: error @ ErrorData::OverloadedError { .. } } => {
+                return Err(forge_domain::Error::ProviderUnavailable(error.to_string()).into());
             }
             Event::Error { error } => {
                 return Err(anyhow::anyhow!("Anthropic API error: {}", error));
@@ -205,6 +261,15 @@ impl TryFrom<ContentBlock> for ChatCompletionMessage {
                     arguments_part: partial_json,
                 })
             }
+            ContentBlock::Thinking { thinking } | ContentBlock::ThinkingDelta { thinking } => {
+                ChatCompletionMessage::default().reasoning(ReasoningPart::Text(thinking))
+            }
+            ContentBlock::SignatureDelta { signature } => {
+                ChatCompletionMessage::default().reasoning(ReasoningPart::Signature(signature))
+            }
+            ContentBlock::RedactedThinking { data } => {
+                ChatCompletionMessage::default().reasoning(ReasoningPart::Redacted(data))
+            }
         };
 
         Ok(result)
@@ -222,6 +287,49 @@ mod tests {
         assert!(matches!(event_data, EventData::Unknown(_)));
     }
 
+    #[test]
+    fn test_context_length_error() {
+        let event = r#"{"type": "error", "error": {"type": "invalid_request_error", "message": "prompt is too long: 210000 tokens > 200000 maximum"}}"#;
+        let event_data = serde_json::from_str::<EventData>(event).unwrap();
+
+        let error = ChatCompletionMessage::try_from(event_data).unwrap_err();
+        assert!(forge_domain::Error::is_context_length_exceeded(&error));
+    }
+
+    #[test]
+    fn test_overloaded_error() {
+        let event =
+            r#"{"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}"#;
+        let event_data = serde_json::from_str::<EventData>(event).unwrap();
+
+        let error = ChatCompletionMessage::try_from(event_data).unwrap_err();
+        assert!(forge_domain::Error::is_provider_unavailable(&error));
+        assert_eq!(
+            error.to_string(),
+            "Provider unavailable: OverloadedError: Overloaded"
+        );
+    }
+
+    #[test]
+    fn test_usage_includes_cached_tokens() {
+        let usage = Usage {
+            input_tokens: Some(50),
+            output_tokens: Some(10),
+            cache_creation_input_tokens: Some(200),
+            cache_read_input_tokens: Some(1000),
+        };
+
+        let actual = forge_domain::Usage::from(usage);
+        let expected = forge_domain::Usage {
+            prompt_tokens: 1250,
+            completion_tokens: 10,
+            total_tokens: 1260,
+            cache_read_tokens: 1000,
+            cache_write_tokens: 200,
+        };
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn test_event_deser() {
         let tests = vec![
@@ -244,7 +352,12 @@ mod tests {
                         model: "claude-3-opus-20240229".to_string(),
                         stop_reason: None,
                         stop_sequence: None,
-                        usage: Usage { input_tokens: Some(10), output_tokens: Some(1) },
+                        usage: Usage {
+                            input_tokens: Some(10),
+                            output_tokens: Some(1),
+                            cache_creation_input_tokens: None,
+                            cache_read_input_tokens: None,
+                        },
                     },
                 },
             ),
@@ -273,6 +386,22 @@ mod tests {
                     delta: ContentBlock::TextDelta { text: "!".to_string() },
                 },
             ),
+            (
+                "thinking_delta",
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"thinking_delta","thinking":"Let me add"}}"#,
+                Event::ContentBlockDelta {
+                    index: 0,
+                    delta: ContentBlock::ThinkingDelta { thinking: "Let me add".to_string() },
+                },
+            ),
+            (
+                "signature_delta",
+                r#"{"type":"content_block_delta","index":0,"delta":{"type":"signature_delta","signature":"EqQBCgIYAhIM"}}"#,
+                Event::ContentBlockDelta {
+                    index: 0,
+                    delta: ContentBlock::SignatureDelta { signature: "EqQBCgIYAhIM".to_string() },
+                },
+            ),
             (
                 "content_block_stop",
                 r#"{"type":"content_block_stop","index":0}"#,
@@ -283,7 +412,12 @@ mod tests {
                 r#"{"type":"message_delta","delta":{"stop_reason":"end_turn","stop_sequence":null},"usage":{"output_tokens":12}}"#,
                 Event::MessageDelta {
                     delta: MessageDelta { stop_reason: StopReason::EndTurn, stop_sequence: None },
-                    usage: Usage { input_tokens: None, output_tokens: Some(12) },
+                    usage: Usage {
+                        input_tokens: None,
+                        output_tokens: Some(12),
+                        cache_creation_input_tokens: None,
+                        cache_read_input_tokens: None,
+                    },
                 },
             ),
             (
@@ -1,19 +1,27 @@
 mod anthropic;
 mod open_router;
+mod retry;
 
 use anthropic::Anthropic;
-use forge_domain::{Provider, ProviderService};
+use anyhow::Context as _;
+use forge_domain::{Provider, ProviderService, RetryConfig};
 use open_router::{OpenRouter, Provider as OpenRouterProvider};
+use reqwest::Url;
 
 #[derive(Debug)]
 pub struct ProviderBuilder {
     url: String,
     api_key: Option<String>,
+    retry_config: RetryConfig,
 }
 
 impl ProviderBuilder {
     pub fn from_url<S: Into<String>>(url: S) -> Self {
-        Self { url: url.into(), api_key: None }
+        Self {
+            url: url.into(),
+            api_key: None,
+            retry_config: RetryConfig::default(),
+        }
     }
 
     pub fn with_key<S: Into<String>>(mut self, key: S) -> Self {
@@ -21,29 +29,44 @@ impl ProviderBuilder {
         self
     }
 
+    pub fn with_retry_config(mut self, retry_config: RetryConfig) -> Self {
+        self.retry_config = retry_config;
+        self
+    }
+
     pub fn build(self) -> Result<Box<dyn ProviderService>, anyhow::Error> {
         let provider = Provider::from_url(&self.url)
             .ok_or_else(|| anyhow::anyhow!("Failed to detect provider from URL: {}", self.url))?;
-        let api_key = self
-            .api_key
-            .ok_or_else(|| anyhow::anyhow!("API key is required for provider: {}", provider))?;
+        let api_key = match (&provider, self.api_key) {
+            // note: self-hosted servers usually don't require an API key.
+            (Provider::OpenAICompatible(_), key) => key.filter(|key| !key.is_empty()),
+            (_, Some(key)) => Some(key),
+            (_, None) => anyhow::bail!("API key is required for provider: {}", provider),
+        };
+        let open_router = |provider: OpenRouterProvider| {
+            let builder = OpenRouter::builder()
+                .provider(provider)
+                .retry_config(self.retry_config.clone());
+            match api_key.clone() {
+                Some(api_key) => builder.api_key(api_key),
+                None => builder,
+            }
+            .build()
+        };
+
         Ok(match provider {
-            Provider::OpenRouter => Box::new(
-                OpenRouter::builder()
-                    .provider(OpenRouterProvider::OpenRouter)
-                    .api_key(api_key)
-                    .build()?,
-            ),
-            Provider::OpenAI => Box::new(
-                OpenRouter::builder()
-                    .provider(OpenRouterProvider::OpenAI)
-                    .api_key(api_key)
-                    .build()?,
-            ),
+            Provider::OpenRouter => Box::new(open_router(OpenRouterProvider::OpenRouter)?),
+            Provider::OpenAI => Box::new(open_router(OpenRouterProvider::OpenAI)?),
+            Provider::OpenAICompatible(ref url) => {
+                let url = Url::parse(url)
+                    .with_context(|| format!("Failed to parse provider URL: {}", url))?;
+                Box::new(open_router(OpenRouterProvider::OpenAICompatible(url))?)
+            }
             Provider::Anthropic => Box::new(
                 Anthropic::builder()
-                    .api_key(api_key)
+                    .api_key(api_key.unwrap_or_default())
                     .base_url(self.url)
+                    .retry_config(self.retry_config)
                     .build()?,
             ),
         })
@@ -2,26 +2,29 @@ use anyhow::{Context as _, Result};
 use derive_setters::Setters;
 use forge_domain::{
     self, ChatCompletionMessage, Context as ChatContext, Model, ModelId, Parameters,
-    ProviderService, ResultStream,
+    ProviderService, ResultStream, RetryConfig,
 };
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::{Client, Url};
-use reqwest_eventsource::{Event, RequestBuilderExt};
+use reqwest_eventsource::Event;
 use tokio_stream::StreamExt;
 use tracing::debug;
 
+use super::error::Error;
 use super::model::{ListModelResponse, OpenRouterModel};
 use super::parameters::ParameterResponse;
 use super::provider::Provider;
 use super::request::OpenRouterRequest;
 use super::response::OpenRouterResponse;
 use crate::open_router::transformers::{ProviderPipeline, Transformer};
+use crate::retry::{event_stream, unavailable, Attempt};
 
 #[derive(Debug, Default, Clone, Setters)]
 #[setters(into, strip_option)]
 pub struct OpenRouterBuilder {
     api_key: Option<String>,
     provider: Option<Provider>,
+    retry_config: Option<RetryConfig>,
 }
 
 impl OpenRouterBuilder {
@@ -30,7 +33,12 @@ impl OpenRouterBuilder {
         let provider = self
             .provider
             .ok_or_else(|| anyhow::anyhow!("Provider is required"))?;
-        Ok(OpenRouter { client, api_key: self.api_key, provider })
+        Ok(OpenRouter {
+            client,
+            api_key: self.api_key,
+            provider,
+            retry_config: self.retry_config.unwrap_or_default(),
+        })
     }
 }
 
@@ -39,6 +47,7 @@ pub struct OpenRouter {
     client: Client,
     api_key: Option<String>,
     provider: Provider,
+    retry_config: RetryConfig,
 }
 
 impl OpenRouter {
@@ -78,6 +87,21 @@ impl OpenRouter {
     }
 }
 
+/// Reports upstream errors about the context being too long as
+/// [`forge_domain::Error::ContextLengthExceeded`], so that they can be
+/// recovered from.
+fn into_domain_error(error: Error) -> anyhow::Error {
+    match error {
+        Error::Upstream(ref response) => {
+            match forge_domain::Error::context_length_exceeded(&response.message) {
+                Some(error) => error.into(),
+                None => error.into(),
+            }
+        }
+        error => error.into(),
+    }
+}
+
 #[async_trait::async_trait]
 impl ProviderService for OpenRouter {
     async fn chat(
@@ -93,16 +117,23 @@ impl ProviderService for OpenRouter {
 
         let url = self.url("chat/completions")?;
         debug!(url = %url, model = %model_id, "Connecting to OpenRouter API");
-        let es = self
-            .client
-            .post(url)
-            .headers(self.headers())
-            .json(&request)
-            .eventsource()?;
-
-        let stream = es
-            .take_while(|message| !matches!(message, Err(reqwest_eventsource::Error::StreamEnded)))
-            .then(|event| async {
+        let request = self.client.post(url).headers(self.headers()).json(&request);
+
+        let stream = event_stream(self.retry_config.clone(), request)?
+            .take_while(|attempt| {
+                !matches!(
+                    attempt,
+                    Attempt::Event(Err(reqwest_eventsource::Error::StreamEnded))
+                )
+            })
+            .then(|attempt| async {
+                let event = match attempt {
+                    Attempt::Retry(retry) => {
+                        return Some(Ok(ChatCompletionMessage::default().retry(retry)))
+                    }
+                    Attempt::Exhausted(error) => return Some(Err(unavailable(error).await)),
+                    Attempt::Event(event) => event,
+                };
                 match event {
                     Ok(event) => match event {
                         Event::Open => None,
@@ -114,6 +145,7 @@ impl ProviderService for OpenRouter {
                                 .with_context(|| "Failed to parse OpenRouter response")
                                 .and_then(|message| {
                                     ChatCompletionMessage::try_from(message.clone())
+                                        .map_err(into_domain_error)
                                         .with_context(|| "Failed to create completion message")
                                 }),
                         ),
@@ -126,6 +158,7 @@ impl ProviderService for OpenRouter {
                             .with_context(|| "Failed to parse OpenRouter response")
                             .and_then(|message| {
                                 ChatCompletionMessage::try_from(message.clone())
+                                    .map_err(into_domain_error)
                                     .with_context(|| "Failed to create completion message")
                             })
                             .with_context(|| "Failed with invalid status code"),
@@ -137,6 +170,7 @@ impl ProviderService for OpenRouter {
                             .with_context(|| "Failed to parse OpenRouter response")
                             .and_then(|message| {
                                 ChatCompletionMessage::try_from(message.clone())
+                                    .map_err(into_domain_error)
                                     .with_context(|| "Failed to create completion message")
                             })
                             .with_context(|| "Failed with invalid content type"),
@@ -171,10 +205,10 @@ impl ProviderService for OpenRouter {
 
     async fn parameters(&self, model: &ModelId) -> Result<Parameters> {
         match self.provider {
-            Provider::OpenAI => {
+            Provider::OpenAI | Provider::OpenAICompatible(_) => {
                 // TODO: open-ai provider doesn't support parameters endpoint, so we return true
                 // for now.
-                return Ok(Parameters { tool_supported: true });
+                return Ok(Parameters::new(true));
             }
             Provider::OpenRouter => {
                 // // For Eg: https://openrouter.ai/api/v1/parameters/google/gemini-pro-1.5-exp
@@ -202,6 +236,8 @@ impl ProviderService for OpenRouter {
                         .iter()
                         .flat_map(|parameter| parameter.iter())
                         .any(|parameter| parameter == "tools"),
+                    supported_parameters: response.data.supported_parameters,
+                    max_temperature: None,
                 })
             }
         }
@@ -210,11 +246,17 @@ impl ProviderService for OpenRouter {
 
 impl From<OpenRouterModel> for Model {
     fn from(value: OpenRouterModel) -> Self {
+        // note: OpenAI compatible servers only report the id of their models.
+        let name = value.name.unwrap_or_else(|| value.id.to_string());
         Model {
             id: value.id,
-            name: value.name,
+            name,
             description: value.description,
-            context_length: Some(value.context_length),
+            context_length: value.context_length,
+            pricing: value.pricing.and_then(|pricing| pricing.to_domain()),
+            image_input: value
+                .architecture
+                .map(|architecture| architecture.image_input()),
         }
     }
 }
@@ -230,6 +272,7 @@ mod tests {
             client: Client::new(),
             api_key: None,
             provider: Provider::OpenRouter,
+            retry_config: RetryConfig::default(),
         }
     }
 
@@ -306,4 +349,107 @@ mod tests {
         assert!(message.is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_context_length_error() -> Result<()> {
+        let content = serde_json::to_string(&serde_json::json!({
+          "error": {
+            "message": "This endpoint's maximum context length is 16384 tokens",
+            "code": 400
+          }
+        }))
+        .unwrap();
+        let message = serde_json::from_str::<OpenRouterResponse>(&content)
+            .context("Failed to parse response")?;
+        let error = ChatCompletionMessage::try_from(message)
+            .map_err(into_domain_error)
+            .unwrap_err();
+
+        assert!(forge_domain::Error::is_context_length_exceeded(&error));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_models() -> Result<()> {
+        let mut server = mockito::Server::new_async().await;
+        let models = server
+            .mock("GET", "/v1/models")
+            .with_status(200)
+            .with_body(
+                serde_json::json!({
+                    "object": "list",
+                    "data": [
+                        {"id": "llama3.2:latest", "object": "model", "created": 1736889600, "owned_by": "library"}
+                    ]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+
+        let provider = Provider::OpenAICompatible(format!("{}/v1/", server.url()).parse()?);
+        let client = OpenRouter::builder().provider(provider).build()?;
+        let actual = client.models().await?;
+
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].id, ModelId::new("llama3.2:latest"));
+        assert_eq!(actual[0].name, "llama3.2:latest");
+        assert_eq!(actual[0].context_length, None);
+        assert_eq!(actual[0].pricing, None);
+        models.assert_async().await;
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_pricing() -> Result<()> {
+        let model = serde_json::from_value::<OpenRouterModel>(serde_json::json!({
+            "id": "anthropic/claude-3.5-sonnet",
+            "name": "Anthropic: Claude 3.5 Sonnet",
+            "pricing": {
+                "prompt": "0.000003",
+                "completion": "0.000015",
+                "image": "0.0048",
+                "request": "0",
+                "input_cache_read": "0.0000003",
+                "input_cache_write": "0.00000375"
+            }
+        }))?;
+
+        let actual = Model::from(model).pricing;
+        let expected = Some(forge_domain::Pricing {
+            prompt: 0.000003,
+            completion: 0.000015,
+            cache_read: Some(0.0000003),
+            cache_write: Some(0.00000375),
+        });
+        assert_eq!(actual, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_variable_model_pricing_is_unknown() -> Result<()> {
+        let model = serde_json::from_value::<OpenRouterModel>(serde_json::json!({
+            "id": "openrouter/auto",
+            "pricing": {"prompt": "-1", "completion": "-1", "image": "0", "request": "0"}
+        }))?;
+
+        assert_eq!(Model::from(model).pricing, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_model_image_input() -> Result<()> {
+        let model = |modality: &str| {
+            serde_json::from_value::<OpenRouterModel>(serde_json::json!({
+                "id": "openai/gpt-4o",
+                "architecture": {"modality": modality, "tokenizer": "GPT"}
+            }))
+            .map(|model| Model::from(model).image_input)
+        };
+
+        assert_eq!(model("text+image->text")?, Some(true));
+        assert_eq!(model("text->text")?, Some(false));
+        assert_eq!(model("text->image")?, Some(false));
+        Ok(())
+    }
 }
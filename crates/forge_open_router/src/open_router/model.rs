@@ -4,13 +4,13 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct OpenRouterModel {
     pub id: ModelId,
-    pub name: String,
-    pub created: u64,
+    pub name: Option<String>,
+    pub created: Option<u64>,
     pub description: Option<String>,
-    pub context_length: u64,
-    pub architecture: Architecture,
-    pub pricing: Pricing,
-    pub top_provider: TopProvider,
+    pub context_length: Option<u64>,
+    pub architecture: Option<Architecture>,
+    pub pricing: Option<Pricing>,
+    pub top_provider: Option<TopProvider>,
     pub per_request_limits: Option<serde_json::Value>,
 }
 
@@ -21,12 +21,42 @@ pub struct Architecture {
     pub instruct_type: Option<String>,
 }
 
+impl Architecture {
+    /// Whether the model accepts images, e.g. for the modality
+    /// `text+image->text`
+    pub fn image_input(&self) -> bool {
+        self.modality
+            .split("->")
+            .next()
+            .is_some_and(|input| input.split('+').any(|modality| modality == "image"))
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Pricing {
     pub prompt: String,
     pub completion: String,
     pub image: String,
     pub request: String,
+    pub input_cache_read: Option<String>,
+    pub input_cache_write: Option<String>,
+}
+
+impl Pricing {
+    /// The prices in USD per token. Models with variable prices report a
+    /// negative price, which is treated as unknown.
+    pub fn to_domain(&self) -> Option<forge_domain::Pricing> {
+        fn price(value: &str) -> Option<f64> {
+            value.parse::<f64>().ok().filter(|price| *price >= 0.0)
+        }
+
+        Some(forge_domain::Pricing {
+            prompt: price(&self.prompt)?,
+            completion: price(&self.completion)?,
+            cache_read: self.input_cache_read.as_deref().and_then(price),
+            cache_write: self.input_cache_write.as_deref().and_then(price),
+        })
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
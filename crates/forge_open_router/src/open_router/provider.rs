@@ -5,6 +5,8 @@ use reqwest::Url;
 pub enum Provider {
     OpenAI,
     OpenRouter,
+    /// Any other server that implements the OpenAI API
+    OpenAICompatible(Url),
 }
 
 impl Provider {
@@ -16,10 +18,15 @@ impl Provider {
         matches!(self, Self::OpenRouter)
     }
 
+    pub fn is_openai_compatible(&self) -> bool {
+        matches!(self, Self::OpenAICompatible(_))
+    }
+
     pub fn base_url(&self) -> Url {
         match self {
             Self::OpenAI => "https://api.openai.com/v1/".parse().unwrap(),
             Self::OpenRouter => "https://openrouter.ai/api/v1/".parse().unwrap(),
+            Self::OpenAICompatible(url) => url.clone(),
         }
     }
 }
@@ -38,5 +45,9 @@ mod tests {
             Provider::OpenRouter.base_url(),
             "https://openrouter.ai/api/v1/".parse().unwrap()
         );
+        assert_eq!(
+            Provider::OpenAICompatible("http://localhost:11434/v1/".parse().unwrap()).base_url(),
+            "http://localhost:11434/v1/".parse().unwrap()
+        );
     }
 }
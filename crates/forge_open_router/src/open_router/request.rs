@@ -1,7 +1,8 @@
 use derive_more::derive::Display;
 use derive_setters::Setters;
 use forge_domain::{
-    Context, ContextMessage, ModelId, Role, ToolCallFull, ToolCallId, ToolDefinition, ToolName,
+    Context, ContextMessage, ModelId, OutputSchema, Role, ToolCallFull, ToolCallId, ToolDefinition,
+    ToolName,
 };
 use serde::{Deserialize, Serialize};
 
@@ -102,6 +103,26 @@ pub struct OpenRouterTool {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ResponseFormat {
     pub r#type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json_schema: Option<JsonSchemaFormat>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+}
+
+impl From<OutputSchema> for ResponseFormat {
+    fn from(value: OutputSchema) -> Self {
+        ResponseFormat {
+            r#type: "json_schema".to_string(),
+            json_schema: Some(JsonSchemaFormat {
+                name: "output".to_string(),
+                schema: value.schema().clone(),
+            }),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -195,6 +216,7 @@ impl From<ToolDefinition> for OpenRouterTool {
 
 impl From<Context> for OpenRouterRequest {
     fn from(request: Context) -> Self {
+        let sampling = request.sampling.unwrap_or_default();
         OpenRouterRequest {
             messages: {
                 let messages = request
@@ -219,15 +241,15 @@ impl From<Context> for OpenRouterRequest {
             },
             model: None,
             prompt: Default::default(),
-            response_format: Default::default(),
-            stop: Default::default(),
+            response_format: request.output_schema.map(ResponseFormat::from),
+            stop: (!sampling.stop.is_empty()).then_some(sampling.stop),
             stream: Default::default(),
-            max_tokens: Default::default(),
-            temperature: Default::default(),
+            max_tokens: sampling.max_tokens,
+            temperature: sampling.temperature,
             tool_choice: request.tool_choice.map(|tc| tc.into()),
             seed: Default::default(),
-            top_p: Default::default(),
-            top_k: Default::default(),
+            top_p: sampling.top_p,
+            top_k: sampling.top_k,
             frequency_penalty: Default::default(),
             presence_penalty: Default::default(),
             repetition_penalty: Default::default(),
@@ -263,7 +285,19 @@ impl From<ContextMessage> for OpenRouterMessage {
         match value {
             ContextMessage::ContentMessage(chat_message) => OpenRouterMessage {
                 role: chat_message.role.into(),
-                content: Some(MessageContent::Text(chat_message.content)),
+                content: Some(match chat_message.images {
+                    Some(images) => MessageContent::Parts(
+                        std::iter::once(ContentPart::Text {
+                            text: chat_message.content,
+                            cache_control: None,
+                        })
+                        .chain(images.iter().map(|image| ContentPart::ImageUrl {
+                            image_url: ImageUrl { url: image.url(), detail: None },
+                        }))
+                        .collect(),
+                    ),
+                    None => MessageContent::Text(chat_message.content),
+                }),
                 name: None,
                 tool_call_id: None,
                 tool_calls: chat_message.tool_calls.map(|tool_calls| {
@@ -306,7 +340,7 @@ pub enum OpenRouterRole {
 #[cfg(test)]
 mod tests {
     use forge_domain::{
-        ContentMessage, ContextMessage, Role, ToolCallFull, ToolCallId, ToolName, ToolResult,
+        ContentMessage, ContextMessage, Image, Role, ToolCallFull, ToolCallId, ToolName, ToolResult,
     };
     use insta::assert_json_snapshot;
     use serde_json::json;
@@ -319,11 +353,21 @@ mod tests {
             role: Role::User,
             content: "Hello".to_string(),
             tool_calls: None,
+            reasoning: None,
+            images: None,
         });
         let router_message = OpenRouterMessage::from(user_message);
         assert_json_snapshot!(router_message);
     }
 
+    #[test]
+    fn test_user_message_with_images_conversion() {
+        let user_message = ContextMessage::user("What's in this screenshot?")
+            .with_images(vec![Image::new_bytes(b"png", "image/png")]);
+        let router_message = OpenRouterMessage::from(user_message);
+        assert_json_snapshot!(router_message);
+    }
+
     #[test]
     fn test_message_with_special_chars() {
         let xml_content = r#"Here's some XML content:
@@ -340,6 +384,8 @@ mod tests {
             role: Role::User,
             content: xml_content.to_string(),
             tool_calls: None,
+            reasoning: None,
+            images: None,
         });
         let router_message = OpenRouterMessage::from(message);
         assert_json_snapshot!(router_message);
@@ -357,6 +403,8 @@ mod tests {
             role: Role::Assistant,
             content: "Using tool".to_string(),
             tool_calls: Some(vec![tool_call]),
+            reasoning: None,
+            images: None,
         });
         let router_message = OpenRouterMessage::from(assistant_message);
         assert_json_snapshot!(router_message);
@@ -417,4 +465,17 @@ mod tests {
             "\"middle-out\""
         );
     }
+
+    #[test]
+    fn test_response_format_from_output_schema() {
+        let context = Context::default()
+            .add_message(ContextMessage::user("Generate a title"))
+            .output_schema(OutputSchema::new(json!({
+                "type": "object",
+                "properties": {"title": {"type": "string"}},
+                "required": ["title"]
+            })));
+        let request = OpenRouterRequest::from(context);
+        assert_json_snapshot!(request.response_format);
+    }
 }
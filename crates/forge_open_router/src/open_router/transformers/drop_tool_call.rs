@@ -54,11 +54,16 @@ mod tests {
                     role: Role::Assistant,
                     content: "Using tool".to_string(),
                     tool_calls: Some(vec![tool_call]),
+                    reasoning: None,
+                    images: None,
                 }),
                 ContextMessage::ToolMessage(tool_result),
             ],
             tools: vec![],
             tool_choice: None,
+            thinking: None,
+            output_schema: None,
+            sampling: None,
         };
 
         let request = OpenRouterRequest::from(context);
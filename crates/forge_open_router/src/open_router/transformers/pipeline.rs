@@ -26,7 +26,8 @@ impl Transformer for ProviderPipeline<'_> {
             .combine(SetCache.except_when_model("mistral|gemini|openai"))
             .when(move |_| self.0.is_open_router());
 
-        let openai_transformers = OpenAITransformer.when(move |_| self.0.is_openai());
+        let openai_transformers =
+            OpenAITransformer.when(move |_| self.0.is_openai() || self.0.is_openai_compatible());
 
         or_transformers
             .combine(openai_transformers)
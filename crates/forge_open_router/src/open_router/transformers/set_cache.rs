@@ -36,9 +36,14 @@ mod tests {
                 role: Role::User,
                 content: "test message".to_string(),
                 tool_calls: None,
+                reasoning: None,
+                images: None,
             })],
             tools: vec![],
             tool_choice: None,
+            thinking: None,
+            output_schema: None,
+            sampling: None,
         };
 
         let request =
@@ -0,0 +1,299 @@
+//! Retries of streaming requests that fail before the first message arrives.
+//!
+//! Once the provider started streaming, a retry would send the tokens that
+//! were already received again, so failures after that point are passed on to
+//! the caller as is.
+
+use std::time::Duration;
+
+use forge_domain::{Retry, RetryConfig};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{RequestBuilder, StatusCode};
+use reqwest_eventsource::retry::Never;
+use reqwest_eventsource::{Error, Event, EventSource, RequestBuilderExt};
+use tokio_stream::{Stream, StreamExt};
+
+/// An item of a retried event stream
+pub enum Attempt {
+    /// The request failed and is sent again after the delay
+    Retry(Retry),
+    /// The request failed with a retryable error on every attempt
+    Exhausted(Error),
+    Event(Result<Event, Error>),
+}
+
+enum State {
+    Connect {
+        request: RequestBuilder,
+        attempt: usize,
+        delay: Duration,
+    },
+    Stream(EventSource),
+}
+
+/// Sends the request and streams the server-sent events of the response.
+/// Rate limits, server errors and dropped connections that happen before the
+/// first message are retried with an exponential backoff.
+pub fn event_stream(
+    config: RetryConfig,
+    request: RequestBuilder,
+) -> anyhow::Result<impl Stream<Item = Attempt>> {
+    // Every attempt sends a copy of the request
+    if request.try_clone().is_none() {
+        anyhow::bail!("Failed to send the request: its body can't be retried");
+    }
+
+    let state = State::Connect { request, attempt: 1, delay: Duration::ZERO };
+    Ok(futures::stream::unfold(state, move |state| {
+        let config = config.clone();
+        async move {
+            match state {
+                State::Connect { request, attempt, delay } => {
+                    tokio::time::sleep(delay).await;
+                    connect(&config, request, attempt).await
+                }
+                State::Stream(mut es) => {
+                    let event = es.next().await?;
+                    Some((Attempt::Event(event), State::Stream(es)))
+                }
+            }
+        }
+    }))
+}
+
+async fn connect(
+    config: &RetryConfig,
+    request: RequestBuilder,
+    attempt: usize,
+) -> Option<(Attempt, State)> {
+    // Checked upfront, cloning the request can't fail
+    let mut es = request.try_clone()?.eventsource().ok()?;
+
+    // The event source would otherwise reconnect on its own and resend the
+    // request, even after part of the response was streamed
+    es.set_retry_policy(Box::new(Never));
+
+    let first = loop {
+        match es.next().await {
+            Some(Ok(Event::Open)) => continue,
+            first => break first,
+        }
+    };
+
+    match first {
+        Some(Err(error)) if attempt < config.max_attempts && is_retryable(&error) => {
+            es.close();
+            let delay = retry_after(config, &error).unwrap_or_else(|| backoff(config, attempt));
+            let retry = Retry {
+                attempt,
+                max_attempts: config.max_attempts,
+                delay,
+                reason: error.to_string(),
+            };
+            tracing::warn!(retry = %retry, "Retrying provider request");
+
+            let next = State::Connect { request, attempt: attempt + 1, delay };
+            Some((Attempt::Retry(retry), next))
+        }
+        Some(Err(error)) if is_retryable(&error) => {
+            es.close();
+            Some((Attempt::Exhausted(error), State::Stream(es)))
+        }
+        Some(first) => Some((Attempt::Event(first), State::Stream(es))),
+        None => None,
+    }
+}
+
+/// Converts the error of a request that failed on every attempt into a
+/// [`forge_domain::Error::ProviderUnavailable`], so that the caller can move on
+/// to another model
+pub async fn unavailable(error: Error) -> anyhow::Error {
+    let reason = match error {
+        Error::InvalidStatusCode(status, response) => {
+            let body = response.text().await.unwrap_or_default();
+            format!("{status}: {}", body.trim())
+        }
+        error => error.to_string(),
+    };
+    forge_domain::Error::ProviderUnavailable(reason.trim().to_string()).into()
+}
+
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::InvalidStatusCode(status, _) => {
+            *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+        }
+        Error::Transport(_) | Error::StreamEnded => true,
+        _ => false,
+    }
+}
+
+/// The delay requested by the provider, in seconds. It is capped at the
+/// longest backoff, so that a provider can't stall the client for hours.
+fn retry_after(config: &RetryConfig, error: &Error) -> Option<Duration> {
+    match error {
+        Error::InvalidStatusCode(_, response) => response
+            .headers()
+            .get(RETRY_AFTER)?
+            .to_str()
+            .ok()?
+            .trim()
+            .parse::<u64>()
+            .ok()
+            .map(|delay| {
+                Duration::from_secs(delay).min(Duration::from_millis(config.max_backoff_ms))
+            }),
+        _ => None,
+    }
+}
+
+/// Exponential backoff with jitter, so that concurrent requests that failed
+/// together don't retry at the same time
+fn backoff(config: &RetryConfig, attempt: usize) -> Duration {
+    let exponent = attempt.saturating_sub(1).min(16) as u32;
+    let delay = config
+        .initial_backoff_ms
+        .saturating_mul(2u64.pow(exponent))
+        .min(config.max_backoff_ms);
+
+    // Somewhere between half and the full delay
+    Duration::from_millis(delay / 2 + fastrand::u64(0..=delay / 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retries_before_first_message() {
+        let mut server = mockito::Server::new_async().await;
+        let rate_limited = server
+            .mock("POST", "/chat")
+            .with_status(429)
+            .with_header("retry-after", "0")
+            .expect(1)
+            .create_async()
+            .await;
+        let success = server
+            .mock("POST", "/chat")
+            .with_status(200)
+            .with_header("content-type", "text/event-stream")
+            .with_body("data: hello\n\n")
+            .expect(1)
+            .create_async()
+            .await;
+
+        let request = reqwest::Client::new().post(format!("{}/chat", server.url()));
+        let actual = event_stream(RetryConfig::default(), request)
+            .unwrap()
+            .map(|attempt| match attempt {
+                Attempt::Retry(retry) => format!("retry {} after {:?}", retry.attempt, retry.delay),
+                Attempt::Exhausted(error) => format!("exhausted {error}"),
+                Attempt::Event(Ok(Event::Message(message))) => message.data,
+                Attempt::Event(event) => format!("{event:?}"),
+            })
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+
+        let expected = vec!["retry 1 after 0ns".to_string(), "hello".to_string()];
+        assert_eq!(actual, expected);
+        rate_limited.assert_async().await;
+        success.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_capped() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/chat")
+            .with_status(429)
+            .with_header("retry-after", "3600")
+            .create_async()
+            .await;
+
+        let config = RetryConfig::default().max_backoff_ms(10u64);
+        let request = reqwest::Client::new().post(format!("{}/chat", server.url()));
+        let stream = event_stream(config, request).unwrap();
+        let actual = match Box::pin(stream).next().await {
+            Some(Attempt::Retry(retry)) => retry.delay,
+            _ => panic!("Expected the request to be retried"),
+        };
+
+        assert_eq!(actual, Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let mut server = mockito::Server::new_async().await;
+        let unavailable = server
+            .mock("POST", "/chat")
+            .with_status(503)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let config = RetryConfig::default()
+            .max_attempts(2usize)
+            .initial_backoff_ms(0u64);
+        let request = reqwest::Client::new().post(format!("{}/chat", server.url()));
+        let actual = event_stream(config, request)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(actual.len(), 2);
+        assert!(matches!(actual[0], Attempt::Retry(_)));
+        assert!(matches!(
+            actual[1],
+            Attempt::Exhausted(Error::InvalidStatusCode(StatusCode::SERVICE_UNAVAILABLE, _))
+        ));
+        unavailable.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_describes_response() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/chat")
+            .with_status(529)
+            .with_body("Overloaded\n")
+            .create_async()
+            .await;
+
+        let request = reqwest::Client::new().post(format!("{}/chat", server.url()));
+        let stream = event_stream(RetryConfig::default().max_attempts(1usize), request).unwrap();
+        let error = match Box::pin(stream).next().await {
+            Some(Attempt::Exhausted(error)) => unavailable(error).await,
+            _ => panic!("Expected the request to be exhausted"),
+        };
+
+        assert!(forge_domain::Error::is_provider_unavailable(&error));
+        assert_eq!(
+            error.to_string(),
+            "Provider unavailable: 529 <unknown status code>: Overloaded"
+        );
+    }
+
+    #[test]
+    fn test_backoff_grows_until_max() {
+        let config = RetryConfig::default()
+            .initial_backoff_ms(1000u64)
+            .max_backoff_ms(5000u64);
+
+        let actual = (1..=5)
+            .map(|attempt| backoff(&config, attempt).as_millis() as u64)
+            .collect::<Vec<_>>();
+
+        let bounds = [1000, 2000, 4000, 5000, 5000];
+        for (delay, max) in actual.into_iter().zip(bounds) {
+            assert!(
+                (max / 2..=max).contains(&delay),
+                "{delay} not in {}..={max}",
+                max / 2
+            );
+        }
+    }
+}
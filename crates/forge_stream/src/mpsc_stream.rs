@@ -3,10 +3,12 @@ use std::future::Future;
 use futures::Stream;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
 
 pub struct MpscStream<T> {
     join_handle: JoinHandle<()>,
     receiver: Receiver<T>,
+    cancel: CancellationToken,
 }
 
 impl<T> MpscStream<T> {
@@ -14,9 +16,30 @@ impl<T> MpscStream<T> {
     where
         F: (FnOnce(Sender<T>) -> S) + Send + 'static,
         S: Future<Output = ()> + Send + 'static,
+    {
+        Self::spawn_cancellable(|tx, _| f(tx))
+    }
+
+    /// Spawns a task that is expected to wind down on its own once the token
+    /// is cancelled, while the stream keeps receiving its last messages.
+    pub fn spawn_cancellable<F, S>(f: F) -> MpscStream<T>
+    where
+        F: (FnOnce(Sender<T>, CancellationToken) -> S) + Send + 'static,
+        S: Future<Output = ()> + Send + 'static,
     {
         let (tx, rx) = tokio::sync::mpsc::channel(1);
-        MpscStream { join_handle: tokio::spawn(f(tx)), receiver: rx }
+        let cancel = CancellationToken::new();
+        MpscStream {
+            join_handle: tokio::spawn(f(tx, cancel.clone())),
+            receiver: rx,
+            cancel,
+        }
+    }
+
+    /// Asks the task to stop. Unlike dropping the stream, this gives the task
+    /// a chance to finish cleanly.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
     }
 }
 
@@ -60,6 +83,19 @@ mod test {
         assert_eq!(result, Some("test message"));
     }
 
+    #[tokio::test]
+    async fn test_cancel_lets_task_finish() {
+        let stream = MpscStream::spawn_cancellable(|tx, cancel| async move {
+            cancel.cancelled().await;
+            tx.send("cancelled").await.unwrap();
+        });
+
+        stream.cancel();
+
+        let actual = stream.collect::<Vec<_>>().await;
+        assert_eq!(actual, vec!["cancelled"]);
+    }
+
     #[tokio::test]
     async fn test_drop_aborts_task() {
         // Pause time to control it manually